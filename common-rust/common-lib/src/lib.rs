@@ -12,10 +12,61 @@ pub struct AgentError {
 // Add this struct to represent the file response
 #[derive(Schema, Clone)]
 pub struct PdfFile {
+    pub status: u16, // 200, or the error status when data is the error text
     pub content_type: String,
     pub data: Vec<u8>,
 }
 
+// A manual with its cache validator. Status is 304 with no data when the
+// If-None-Match sent with the request still matches the ETag.
+#[derive(Schema, Clone)]
+pub struct CachedPdfFile {
+    pub status: u16,
+    pub etag: String, // quoted, empty when the manual failed to build
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+// The HTTP status for a failed request, saying whose fault it was
+fn error_status(code: &str) -> u16 {
+    match code {
+        "UNKNOWN_TEMPLATE" | "NO_LESSONS" | "LESSON_NOT_FOUND" | "NO_EXAM_QUESTIONS" => 404,
+        code if code.starts_with("INVALID_") => 400,
+        _ => 500,
+    }
+}
+
+// A file that failed to build, as the error text
+impl From<AgentError> for PdfFile {
+    fn from(err: AgentError) -> Self {
+        PdfFile {
+            status: error_status(&err.code),
+            content_type: "text/plain".to_string(),
+            data: err.message.into_bytes(),
+        }
+    }
+}
+
+// A manual that failed to build, as the error text
+impl From<AgentError> for CachedPdfFile {
+    fn from(err: AgentError) -> Self {
+        CachedPdfFile {
+            status: error_status(&err.code),
+            etag: String::new(),
+            content_type: "text/plain".to_string(),
+            data: err.message.into_bytes(),
+        }
+    }
+}
+
+// One rendered page of a lesson preview
+#[derive(Schema, Clone)]
+pub struct PreviewPage {
+    pub page_number: u32,
+    pub content_type: String, // "image/png"
+    pub data: Vec<u8>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Schema)]
 pub struct TopicRecord {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -29,6 +80,52 @@ pub struct TopicRecord {
     pub context: Option<String>,
 }
 
+// Everything that identifies the publishing school in the manuals. One profile is
+// stored per school; schools without one get the Johnethel defaults.
+#[derive(Debug, Clone, Serialize, Deserialize, Schema)]
+#[serde(default)]
+pub struct SchoolBranding {
+    pub name: String,       // e.g. "Johnethel School"
+    pub short_name: String, // running header and fallback watermark text, e.g. "JOHNETHEL"
+    pub motto: String,
+    pub address: String,
+    pub phones: Vec<String>,
+    pub email: String,
+    pub editions: Vec<String>,    // years of each edition, oldest first
+    pub logo_tag: Option<String>, // images from the lesson asset library
+    pub watermark_tag: Option<String>,
+    pub primary_colour: String, // hex, e.g. "#1f3864"
+    pub accent_colour: String,
+    pub subject_covers: Vec<SubjectCover>, // subjects without one use the primary colour
+}
+
+// How a subject's manual covers look, matched on the subject name
+#[derive(Debug, Clone, Serialize, Deserialize, Schema)]
+pub struct SubjectCover {
+    pub subject: String,
+    pub colour: String,              // hex, e.g. "#2e7d32"
+    pub artwork_tag: Option<String>, // image from the lesson asset library
+}
+
+impl Default for SchoolBranding {
+    fn default() -> Self {
+        SchoolBranding {
+            name: "Johnethel School".to_string(),
+            short_name: "JOHNETHEL".to_string(),
+            motto: "Grooming Future Leaders for Excellence".to_string(),
+            address: "Omolabake House, beside Town Planning Office, Oke Anu, Ogbomoso, Oyo State, Nigeria".to_string(),
+            phones: vec!["08036674624".to_string(), "09039465940".to_string()],
+            email: "johnethelschool1@gmail.com".to_string(),
+            editions: vec!["2025".to_string(), "2026".to_string()],
+            logo_tag: None,
+            watermark_tag: Some("johnethel-watermark".to_string()),
+            primary_colour: "#000000".to_string(),
+            accent_colour: "#000000".to_string(),
+            subject_covers: Vec::new(),
+        }
+    }
+}
+
 impl From<String> for AgentError {
     fn from(err: String) -> Self {
         AgentError {
@@ -45,11 +142,99 @@ pub trait ContentAgent {
 
     async fn content_generator(&mut self, table: String) -> Result<Vec<String>, AgentError>;
 
+    async fn translate_lessons(
+        &mut self,
+        subject: String,
+        class: String,
+        language: String,
+    ) -> Result<Vec<String>, AgentError>;
+
+    // Store an image in the lesson asset library under the tag lessons refer to it by
+    async fn upload_asset(
+        &mut self,
+        tag: String,
+        content_type: String,
+        data: Vec<u8>,
+    ) -> Result<String, AgentError>;
+
     async fn test_sleep(&mut self) -> String;
 }
 
 #[agent_definition]
 pub trait PdfAgent {
     fn new(name: String) -> Self;
-    async fn pdf_generator(&mut self, class: String, subject: String, mode: String) -> PdfFile;
+    async fn pdf_generator(
+        &mut self,
+        class: String,
+        subject: String,
+        mode: String,
+        // Query options, all optional so the bare route keeps working
+        question_bank: Option<bool>,   // none for no question bank
+        language: Option<String>,      // none for English only
+        term: Option<String>,          // none for the whole year, else e.g. "first"
+        weeks: Option<String>,         // none for every week, else e.g. "3" or "3-5"
+        lesson_ids: Option<String>,    // none for every lesson, else comma separated record ids
+        template: Option<String>,      // registry name, optionally "@version"; none for the default
+        if_none_match: Option<String>, // If-None-Match header, the ETag of the client's copy
+    ) -> CachedPdfFile;
+    async fn answer_sheet_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        term: String,
+        question_count: u32,
+        option_count: u32,
+    ) -> PdfFile;
+    async fn scheme_of_work_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        topics_table: String,
+    ) -> PdfFile;
+    // PNG images of the first pages of one lesson, as it will print in the manual
+    async fn lesson_preview(
+        &mut self,
+        subject: String,
+        class: String,
+        lesson_id: String,
+        mode: String,
+        page_count: u32,
+    ) -> Result<Vec<PreviewPage>, AgentError>;
+    // Front cover for a subject's manual, from the school branding
+    async fn cover_generator(&mut self, subject: String, class: String, mode: String) -> PdfFile;
+    // Manual, or a subset of its lessons, as a single HTML page for the intranet
+    async fn html_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        mode: String,
+        language: Option<String>,
+        term: Option<String>,
+        weeks: Option<String>,
+        lesson_ids: Option<String>,
+    ) -> PdfFile;
+    // Lessons as a Word document teachers can edit before printing
+    async fn docx_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        mode: String,
+        language: Option<String>,
+        term: Option<String>,
+        weeks: Option<String>,
+        lesson_ids: Option<String>,
+    ) -> PdfFile;
+    async fn get_branding(&mut self) -> Result<SchoolBranding, AgentError>;
+    async fn set_branding(&mut self, branding: SchoolBranding) -> Result<String, AgentError>;
+    // Check a typst template against a fixture lesson and store it as the name's next version
+    async fn upload_template(&mut self, name: String, source: String)
+        -> Result<String, AgentError>;
+    async fn list_templates(&mut self) -> Result<Vec<String>, AgentError>;
+    // Characters in the stored lessons (and their translation) that no loaded font can render
+    async fn font_preflight(
+        &mut self,
+        subject: String,
+        class: String,
+        language: String,
+    ) -> Result<Vec<String>, AgentError>;
 }
@@ -0,0 +1,258 @@
+use baml_client::models::CompleteLessonContent;
+use derive_typst_intoval::{IntoDict, IntoValue};
+use typst::foundations::{Dict, IntoValue};
+
+use crate::utils::markup::sanitize;
+use crate::utils::parse_term;
+use crate::utils::pdf_engine::{class_year_number, compile_pdf, term_to_string};
+use crate::AgentError;
+
+// File paths - these should be in your Golem agent's filesystem
+static ANSWER_SHEET_TEMPLATE_PATH: &str = "/templates/answer_sheet.typ";
+
+// Number of digit columns in the candidate number grid
+const CANDIDATE_NUMBER_DIGITS: i32 = 6;
+
+// Lesson MCQs have options A to C
+const OPTION_LETTERS: &[&str] = &["A", "B", "C"];
+
+pub fn answer_sheet_engine(
+    lessons: Vec<CompleteLessonContent>,
+    subject_name: &str,
+    class_year: &str,
+    term: &str,          // e.g. "first", "FIRST" or "1st Term"
+    question_count: u32, // number of questions on the exam
+    option_count: u32,   // bubbles per question, always 3 (A-C) like the lesson MCQs
+    school_name: &str,
+) -> Result<Vec<u8>, AgentError> {
+    if option_count as usize != OPTION_LETTERS.len() {
+        return Err(AgentError {
+            message: format!(
+                "Exam questions have options A to C, so answer sheets have 3 options, got {}",
+                option_count
+            ),
+            code: "INVALID_OPTION_COUNT".to_string(),
+        });
+    }
+
+    let term = term_to_string(parse_term(term)?);
+    let questions = compose_exam(lessons, &term, question_count as usize);
+    if questions.is_empty() {
+        return Err(AgentError {
+            message: format!("No multiple choice questions found for {} term", term),
+            code: "NO_EXAM_QUESTIONS".to_string(),
+        });
+    }
+
+    let input = AnswerSheetInput {
+        school_name: school_name.to_string(),
+        subject_name: subject_name.to_string(),
+        class_year: class_year_number(class_year).to_string(),
+        term,
+        options: OPTION_LETTERS.iter().map(|o| o.to_string()).collect(),
+        candidate_number_digits: CANDIDATE_NUMBER_DIGITS,
+        questions,
+    };
+
+    compile_pdf(ANSWER_SHEET_TEMPLATE_PATH, input)
+}
+
+// Input structure matching answer_sheet.typ expectations
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct AnswerSheetInput {
+    school_name: String,
+    subject_name: String,
+    class_year: String,
+    term: String,
+    options: Vec<String>,
+    candidate_number_digits: i32,
+    questions: Vec<ExamQuestion>, // the question paper, in answer sheet order
+}
+
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct ExamQuestion {
+    week: i32,
+    topic_title: String,
+    question: String,
+    options: Vec<String>, // options A to C
+    answer: String,       // correct option letter, drawn on the teacher key
+}
+
+impl From<AnswerSheetInput> for Dict {
+    fn from(value: AnswerSheetInput) -> Self {
+        value.into_dict()
+    }
+}
+
+// Build the exam by taking MCQs from the term's lessons in turn, so every topic
+// is represented before any lesson contributes a second question. Questions whose
+// answer can't be read are left out rather than printed with a wrong key.
+fn compose_exam(
+    lessons: Vec<CompleteLessonContent>,
+    term: &str,
+    question_count: usize,
+) -> Vec<ExamQuestion> {
+    let mut pools: Vec<_> = lessons
+        .into_iter()
+        .filter_map(|lesson| {
+            if term_to_string(lesson.term) == term {
+                let week = lesson.week;
+                let topic_title = sanitize(&lesson.topic_title);
+                Some(lesson.mcq_questions.into_iter().filter_map(move |q| {
+                    let options = [&q.option_a, &q.option_b, &q.option_c];
+                    let Some(answer) = answer_letter(&q.correct_answer, &options) else {
+                        println!(
+                            "⚠️  Skipping '{}' in week {}: no option matches answer '{}'",
+                            q.question, week, q.correct_answer
+                        );
+                        return None;
+                    };
+                    Some(ExamQuestion {
+                        week,
+                        topic_title: topic_title.clone(),
+                        question: sanitize(&q.question),
+                        options: options.iter().map(|o| sanitize(o)).collect(),
+                        answer,
+                    })
+                }))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let mut questions = Vec::new();
+    while questions.len() < question_count {
+        let mut took_any = false;
+        for pool in pools.iter_mut() {
+            if questions.len() == question_count {
+                break;
+            }
+            if let Some(question) = pool.next() {
+                questions.push(question);
+                took_any = true;
+            }
+        }
+        if !took_any {
+            break;
+        }
+    }
+    questions
+}
+
+// The LLM answers "A", "b", "B) Lagos", "(b)", "Option B", "Answer: C" or just the
+// option text, keep only the option letter. None when it names no option.
+fn answer_letter(correct_answer: &str, options: &[&String]) -> Option<String> {
+    let answer = correct_answer.trim().to_ascii_uppercase();
+    let mut rest = answer.as_str();
+    for prefix in ["ANSWER", ":", "OPTION", "("] {
+        rest = rest.strip_prefix(prefix).unwrap_or(rest).trim_start();
+    }
+    let mut chars = rest.chars();
+    let letter = chars.next().filter(|_| {
+        // "A", "A)" or "A. Lagos", but not the first letter of a word like "Abuja"
+        chars.next().is_none_or(|c| !c.is_alphanumeric())
+    });
+    if let Some(letter) = letter {
+        if let Some(option) = OPTION_LETTERS
+            .iter()
+            .take(options.len())
+            .find(|o| o.starts_with(letter))
+        {
+            return Some(option.to_string());
+        }
+    }
+    options
+        .iter()
+        .position(|o| o.trim().eq_ignore_ascii_case(correct_answer.trim()))
+        .map(|i| OPTION_LETTERS[i].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use baml_client::models::Term;
+    use serde_json::json;
+
+    fn options() -> Vec<String> {
+        vec!["Stone".to_string(), "Goat".to_string(), "Abuja".to_string()]
+    }
+
+    #[test]
+    fn answer_letter_reads_the_option_letter() {
+        let options = options();
+        let options: Vec<&String> = options.iter().collect();
+        for (answer, letter) in [
+            ("A", "A"),
+            ("b", "B"),
+            ("B) Goat", "B"),
+            ("(b)", "B"),
+            ("Option B", "B"),
+            ("Answer: C", "C"),
+            ("c.", "C"),
+            ("goat", "B"),
+            ("Abuja", "C"),
+        ] {
+            assert_eq!(
+                answer_letter(answer, &options).as_deref(),
+                Some(letter),
+                "{}",
+                answer
+            );
+        }
+    }
+
+    #[test]
+    fn answer_letter_rejects_answers_naming_no_option() {
+        let options = options();
+        let options: Vec<&String> = options.iter().collect();
+        for answer in ["D", "(d)", "Option E", "", "Lagos"] {
+            assert_eq!(answer_letter(answer, &options), None, "{}", answer);
+        }
+    }
+
+    fn lesson(week: i32, term: Term, questions: usize) -> CompleteLessonContent {
+        let mut lesson: CompleteLessonContent =
+            serde_json::from_str(include_str!("fixtures/template_lesson.json")).unwrap();
+        lesson.week = week;
+        lesson.term = term;
+        lesson.topic_title = format!("Week {}", week);
+        let mcqs: Vec<_> = (1..=questions)
+            .map(|i| {
+                json!({
+                    "question": format!("W{}Q{}", week, i),
+                    "option_a": "Stone",
+                    "option_b": "Goat",
+                    "option_c": "Chair",
+                    "correct_answer": "B",
+                    "explanation": "",
+                })
+            })
+            .collect();
+        lesson.mcq_questions = serde_json::from_value(json!(mcqs)).unwrap();
+        lesson
+    }
+
+    #[test]
+    fn compose_exam_takes_questions_from_each_lesson_in_turn() {
+        let lessons = vec![
+            lesson(1, Term::First, 3),
+            lesson(2, Term::First, 1),
+            lesson(1, Term::Second, 3),
+            lesson(3, Term::First, 2),
+        ];
+        let questions: Vec<String> = compose_exam(lessons, "FIRST", 5)
+            .into_iter()
+            .map(|q| q.question)
+            .collect();
+        assert_eq!(questions, ["W1Q1", "W2Q1", "W3Q1", "W1Q2", "W3Q2"]);
+    }
+
+    #[test]
+    fn compose_exam_stops_when_the_lessons_run_out() {
+        let lessons = vec![lesson(1, Term::First, 2), lesson(2, Term::First, 1)];
+        let exam = compose_exam(lessons, "FIRST", 10);
+        assert_eq!(exam.len(), 3);
+        assert!(exam.iter().all(|q| q.answer == "B"));
+    }
+}
@@ -0,0 +1,75 @@
+use derive_typst_intoval::{IntoDict, IntoValue};
+use typst::foundations::{Bytes, Dict, IntoValue};
+
+use crate::utils::pdf_engine::{compile_pdf, load_image, TypstBranding};
+use crate::utils::{is_hex_colour, LessonAsset};
+use crate::{AgentError, SchoolBranding, SubjectCover};
+
+// File paths - these should be in your Golem agent's filesystem
+static COVER_TEMPLATE_PATH: &str = "/templates/cover.typ";
+
+pub fn cover_engine(
+    subject_name: &str,
+    class_year: &str,
+    mode: &str, // "pupil" or "teacher"
+    branding: SchoolBranding,
+    assets: Vec<LessonAsset>, // uploaded images for the tags cover_asset_tags returns
+) -> Result<Vec<u8>, AgentError> {
+    let cover = subject_cover(&branding, subject_name);
+    let subject_colour = match cover {
+        Some(cover) if is_hex_colour(&cover.colour) => cover.colour.clone(),
+        _ => branding.primary_colour.clone(),
+    };
+    let artwork = cover
+        .and_then(|c| c.artwork_tag.as_deref())
+        .and_then(|tag| load_image(tag, &assets));
+
+    let input = CoverInput {
+        subject_name: subject_name.to_string(),
+        class_label: class_year.replace('_', " "),
+        mode: mode.to_string(),
+        // Covers carry the current edition only, the copyright page lists them all
+        edition: branding.editions.last().cloned().unwrap_or_default(),
+        subject_colour,
+        artwork,
+        branding: TypstBranding::new(branding, &assets),
+    };
+
+    compile_pdf(COVER_TEMPLATE_PATH, input)
+}
+
+// Images a subject's cover needs from the asset library
+pub fn cover_asset_tags(branding: &SchoolBranding, subject_name: &str) -> Vec<String> {
+    branding
+        .logo_tag
+        .iter()
+        .chain(&branding.watermark_tag)
+        .chain(subject_cover(branding, subject_name).and_then(|c| c.artwork_tag.as_ref()))
+        .cloned()
+        .collect()
+}
+
+fn subject_cover<'a>(branding: &'a SchoolBranding, subject_name: &str) -> Option<&'a SubjectCover> {
+    branding
+        .subject_covers
+        .iter()
+        .find(|c| c.subject.trim().eq_ignore_ascii_case(subject_name.trim()))
+}
+
+// Input structure matching cover.typ expectations
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct CoverInput {
+    subject_name: String,
+    class_label: String, // e.g. "PRIMARY 3" or "JSS 1"
+    mode: String,
+    edition: String,
+    subject_colour: String,
+    artwork: Option<Bytes>,
+    branding: TypstBranding,
+}
+
+impl From<CoverInput> for Dict {
+    fn from(value: CoverInput) -> Self {
+        value.into_dict()
+    }
+}
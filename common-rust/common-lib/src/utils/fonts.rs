@@ -0,0 +1,246 @@
+// The fonts every typst template is compiled with. The body family comes with its
+// bold, italic and bold-italic faces so typst doesn't have to fake them, and fallback
+// fonts cover what it lacks, e.g. ₦. The set can be changed by shipping a
+// /fonts/fonts.json with the same fields as FontSet.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::sync::LazyLock;
+use typst::foundations::Bytes;
+use typst::text::Font;
+
+use crate::AgentError;
+
+static FONT_MANIFEST_PATH: &str = "/fonts/fonts.json";
+
+// Hash of the font files, read once since they only change with a deploy
+static FONTS_FINGERPRINT: LazyLock<String> = LazyLock::new(|| {
+    let mut hasher = Sha256::new();
+    match FontSet::load().read() {
+        Ok(fonts) => fonts
+            .iter()
+            .for_each(|font| hasher.update(Sha256::digest(font))),
+        Err(e) => hasher.update(e.message.as_bytes()),
+    }
+    format!("{:x}", hasher.finalize())
+});
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FontSet {
+    pub regular: String,
+    pub bold: Option<String>,
+    pub italic: Option<String>,
+    pub bold_italic: Option<String>,
+    // Optional OpenType math font for $...$ lesson math, e.g. New Computer Modern Math
+    pub math: Option<String>,
+    // Tried in order for characters the main family can't render
+    pub fallbacks: Vec<String>,
+}
+
+// The fonts golem.yaml ships. Linux Libertine covers the Yoruba, Hausa and Igbo
+// letters but has no ₦, which DejaVu Serif adds. Times New Roman only has a regular
+// face; it stays for uploaded templates that ask for it, and DejaVu Serif covers the
+// ṣ and hooked Hausa letters it lacks. Schools with other faces list them in
+// /fonts/fonts.json.
+impl Default for FontSet {
+    fn default() -> Self {
+        FontSet {
+            regular: "/fonts/linux-libertine.ttf".to_string(),
+            bold: Some("/fonts/linux-libertine-bold.ttf".to_string()),
+            italic: Some("/fonts/linux-libertine-italic.ttf".to_string()),
+            bold_italic: Some("/fonts/linux-libertine-bold-italic.ttf".to_string()),
+            math: Some("/fonts/math.otf".to_string()),
+            fallbacks: vec![
+                "/fonts/times-new-roman.ttf".to_string(),
+                "/fonts/dejavu-serif.ttf".to_string(),
+                "/fonts/dejavu-serif-bold.ttf".to_string(),
+                "/fonts/dejavu-serif-italic.ttf".to_string(),
+                "/fonts/dejavu-serif-bold-italic.ttf".to_string(),
+            ],
+        }
+    }
+}
+
+impl FontSet {
+    pub fn load() -> FontSet {
+        match fs::read_to_string(FONT_MANIFEST_PATH) {
+            Ok(manifest) => serde_json::from_str(&manifest).unwrap_or_else(|e| {
+                println!("⚠️  Invalid font manifest {}: {}", FONT_MANIFEST_PATH, e);
+                FontSet::default()
+            }),
+            Err(_) => FontSet::default(),
+        }
+    }
+
+    // Font file contents, the regular face first. Only the regular face is required,
+    // the others are skipped with a warning when missing.
+    pub(crate) fn read(&self) -> Result<Vec<Vec<u8>>, AgentError> {
+        let regular = fs::read(&self.regular).map_err(|e| AgentError {
+            message: format!("Could not read font file {}: {}", self.regular, e),
+            code: "FONT_READ_ERROR".to_string(),
+        })?;
+
+        let mut fonts = vec![regular];
+        let optional = [&self.bold, &self.italic, &self.bold_italic, &self.math];
+        for path in optional.into_iter().flatten().chain(self.fallbacks.iter()) {
+            match fs::read(path) {
+                Ok(font) => fonts.push(font),
+                Err(_) => println!("⚠️  No font at {}, skipping it", path),
+            }
+        }
+        Ok(fonts)
+    }
+}
+
+// Changes when a deploy ships different fonts, which changes every manual
+pub(crate) fn fonts_fingerprint() -> &'static str {
+    &FONTS_FINGERPRINT
+}
+
+// Characters in the lessons (or translations) that none of the fonts has a glyph for,
+// one line per character listing the lessons that use it
+pub fn font_preflight<T: Serialize>(lessons: &[T]) -> Result<Vec<String>, AgentError> {
+    missing_characters(lessons, &FontSet::load())
+}
+
+fn missing_characters<T: Serialize>(
+    lessons: &[T],
+    font_set: &FontSet,
+) -> Result<Vec<String>, AgentError> {
+    let fonts: Vec<Font> = font_set
+        .read()?
+        .into_iter()
+        .flat_map(|data| Font::iter(Bytes::new(data)))
+        .collect();
+
+    let mut missing: BTreeMap<char, Vec<String>> = BTreeMap::new();
+    for lesson in lessons {
+        let value = serde_json::to_value(lesson).map_err(|e| AgentError {
+            message: format!("Failed to serialize lesson: {}", e),
+            code: "SERIALIZATION_ERROR".to_string(),
+        })?;
+        let title = value
+            .get("topic_title")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        let mut characters: BTreeSet<char> = BTreeSet::new();
+        collect_characters(&value, &mut characters);
+        for c in characters {
+            if fonts.iter().any(|f| f.info().coverage.contains(c as u32)) {
+                continue;
+            }
+            let titles = missing.entry(c).or_default();
+            if !titles.contains(&title) {
+                titles.push(title.clone());
+            }
+        }
+    }
+
+    Ok(missing
+        .into_iter()
+        .map(|(c, titles)| format!("'{}' (U+{:04X}) in {}", c, c as u32, titles.join(", ")))
+        .collect())
+}
+
+fn collect_characters(value: &Value, characters: &mut BTreeSet<char>) {
+    match value {
+        Value::String(text) => characters.extend(
+            text.chars()
+                .filter(|c| !c.is_whitespace() && !c.is_control()),
+        ),
+        Value::Array(items) => items.iter().for_each(|v| collect_characters(v, characters)),
+        Value::Object(fields) => fields
+            .values()
+            .for_each(|v| collect_characters(v, characters)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use typst::text::{FontStyle, FontWeight};
+
+    const COMPONENT_DIR: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../components-rust/generator-functions"
+    );
+
+    // The default font set read from the files golem.yaml maps to its paths. Panics
+    // when a default font has no mapping, i.e. would never load in the component.
+    fn shipped_fonts() -> FontSet {
+        let golem = include_str!("../../../../components-rust/generator-functions/golem.yaml");
+        let lines: Vec<&str> = golem.lines().map(str::trim).collect();
+        let source = |target: &String| {
+            let i = lines
+                .iter()
+                .position(|l| *l == format!("targetPath: {}", target))
+                .unwrap_or_else(|| panic!("{} is not shipped", target));
+            let path = lines[i - 1].trim_start_matches("- sourcePath: .");
+            format!("{}{}", COMPONENT_DIR, path)
+        };
+        let defaults = FontSet::default();
+        FontSet {
+            regular: source(&defaults.regular),
+            bold: defaults.bold.as_ref().map(source),
+            italic: defaults.italic.as_ref().map(source),
+            bold_italic: defaults.bold_italic.as_ref().map(source),
+            math: defaults.math.as_ref().map(source),
+            fallbacks: defaults.fallbacks.iter().map(source).collect(),
+        }
+    }
+
+    fn lesson(title: &str, text: &str) -> Value {
+        json!({ "topic_title": title, "introduction": text })
+    }
+
+    #[test]
+    fn default_fonts_ship_real_bold_and_italic_faces() {
+        let fonts = shipped_fonts();
+        let face = |path: &Option<String>| {
+            let data = fs::read(path.as_ref().unwrap()).unwrap();
+            Font::new(Bytes::new(data), 0).unwrap().info().variant
+        };
+        let bold = face(&fonts.bold);
+        let italic = face(&fonts.italic);
+        let bold_italic = face(&fonts.bold_italic);
+        assert_eq!(bold.weight, FontWeight::BOLD);
+        assert_eq!(italic.style, FontStyle::Italic);
+        assert_eq!(bold_italic.weight, FontWeight::BOLD);
+        assert_eq!(bold_italic.style, FontStyle::Italic);
+        // Every file loads, none is skipped with a warning
+        assert_eq!(fonts.read().unwrap().len(), 5 + fonts.fallbacks.len());
+    }
+
+    #[test]
+    fn shipped_fonts_cover_naira_and_nigerian_letters() {
+        let lessons = [
+            lesson("Owo", "₦500 ni ẹ̀kọ́ náà, ṣé o rí i? Ọmọ"),
+            lesson("Kudi", "ɓaure ɗaya ƙasa ƴa"),
+            lesson("Ego", "ị ụ ṅ Ị Ụ Ṅ"),
+        ];
+        assert_eq!(
+            missing_characters(&lessons, &shipped_fonts()).unwrap(),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn reports_characters_no_font_has() {
+        let lessons = [
+            lesson("Week 1", "漢 and ₦"),
+            lesson("Week 2", "漢"),
+            lesson("Week 3", "plain"),
+        ];
+        assert_eq!(
+            missing_characters(&lessons, &shipped_fonts()).unwrap(),
+            vec!["'漢' (U+6F22) in Week 1, Week 2".to_string()]
+        );
+    }
+}
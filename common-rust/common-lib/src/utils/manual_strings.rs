@@ -0,0 +1,244 @@
+use derive_typst_intoval::{IntoDict, IntoValue};
+use serde::Deserialize;
+use std::fs;
+use typst::foundations::IntoValue;
+
+// Translated string tables live next to the templates as /templates/strings/<language>.json,
+// e.g. yoruba.json. Keys missing from a table fall back to the English text below.
+static STRINGS_DIR: &str = "/templates/strings";
+
+// Replaced with the publishing school's name wherever it appears in a string
+const SCHOOL_PLACEHOLDER: &str = "{school}";
+
+// Boilerplate text of the manual template, supplied per language
+#[derive(Debug, Clone, Deserialize, IntoValue, IntoDict)]
+#[serde(default)]
+pub(crate) struct ManualStrings {
+    // Front matter
+    pub(crate) copyright_title: String,
+    pub(crate) series_title: String,
+    pub(crate) year_label: String,
+    pub(crate) copyright_notice: String,
+    pub(crate) published_by_label: String,
+    pub(crate) first_edition_label: String,
+    pub(crate) second_edition_label: String,
+    pub(crate) later_edition_label: String, // "{n}" is replaced with the edition number
+    pub(crate) permission_requests: String,
+    pub(crate) address_label: String,
+    pub(crate) email_label: String,
+    pub(crate) phone_label: String,
+    pub(crate) attributions_label: String,
+    pub(crate) attributions: String,
+    pub(crate) disclaimer_label: String,
+    pub(crate) disclaimer: String,
+    pub(crate) instructions_title: String,
+    pub(crate) instructions: Vec<InstructionGroup>,
+    pub(crate) table_of_contents: String,
+
+    // Running headers and term pages
+    pub(crate) manual_label: String,
+    pub(crate) first_term: String,
+    pub(crate) second_term: String,
+    pub(crate) third_term: String,
+    pub(crate) ca_test: String,
+    pub(crate) topic_label: String,
+
+    // Lesson sections
+    pub(crate) lesson_objectives: String,
+    pub(crate) previous_knowledge: String,
+    pub(crate) instructional_materials: String,
+    pub(crate) teacher_preparation: String,
+    pub(crate) materials_label: String,
+    pub(crate) duration_label: String,
+    pub(crate) minutes: String,
+    pub(crate) lesson_content: String,
+    pub(crate) conclusion: String,
+    pub(crate) key_points: String,
+    pub(crate) lesson_steps: String,
+    pub(crate) step_label: String,
+    pub(crate) phase_label: String,
+    pub(crate) teacher_actions: String,
+    pub(crate) pupil_activities: String,
+    pub(crate) formative_assessment: String,
+    pub(crate) summative_assessment: String,
+    pub(crate) success_criteria: String,
+    pub(crate) revision_questions: String,
+    pub(crate) theoretical_questions: String,
+    pub(crate) extension_activities: String,
+    pub(crate) remediation: String,
+    pub(crate) references: String,
+    pub(crate) class_activities: String,
+    pub(crate) new_words: String,
+    pub(crate) glossary: String,
+    pub(crate) index: String,
+    pub(crate) exam_questions_bank: String,
+    pub(crate) answer_label: String,
+    pub(crate) explanation_label: String,
+}
+
+// One audience on the "Instructions for Use" page, e.g. "For Teachers"
+#[derive(Debug, Clone, Deserialize, IntoValue, IntoDict)]
+pub(crate) struct InstructionGroup {
+    title: String,
+    sections: Vec<InstructionSection>,
+}
+
+#[derive(Debug, Clone, Deserialize, IntoValue, IntoDict)]
+pub(crate) struct InstructionSection {
+    title: Option<String>, // e.g. "At School:", none when the group has a single list
+    items: Vec<String>,
+}
+
+// Load the string table for a language ("ENGLISH", "YORUBA", ...), falling back to English
+pub(crate) fn manual_strings(language: &str, school: &str) -> ManualStrings {
+    let mut strings = if language == "ENGLISH" {
+        ManualStrings::default()
+    } else {
+        let path = format!("{}/{}.json", STRINGS_DIR, language.to_lowercase());
+        match fs::read_to_string(&path).map(|json| serde_json::from_str::<ManualStrings>(&json)) {
+            Ok(Ok(strings)) => strings,
+            Ok(Err(e)) => {
+                println!("⚠️  Invalid string table {}: {}, using English", path, e);
+                ManualStrings::default()
+            }
+            Err(_) => {
+                println!("⚠️  No string table for {}, using English", language);
+                ManualStrings::default()
+            }
+        }
+    };
+    strings.fill_school(school);
+    strings
+}
+
+impl ManualStrings {
+    fn fill_school(&mut self, school: &str) {
+        for text in [
+            &mut self.series_title,
+            &mut self.copyright_notice,
+            &mut self.attributions,
+            &mut self.disclaimer,
+        ] {
+            *text = text.replace(SCHOOL_PLACEHOLDER, school);
+        }
+    }
+}
+
+impl Default for ManualStrings {
+    fn default() -> Self {
+        ManualStrings {
+            copyright_title: "Copyright Page".to_string(),
+            series_title: "{school} Manual Series".to_string(),
+            year_label: "YEAR".to_string(),
+            copyright_notice: "No part of this publication may be reproduced, distributed, or transmitted in any form or by any means, including photocopying, recording, or other electronic or mechanical methods, without the prior written permission of {school}, except in the case of brief quotations embodied in critical reviews and certain other non-commercial uses permitted by copyright law.".to_string(),
+            published_by_label: "Published by:".to_string(),
+            first_edition_label: "First Edition:".to_string(),
+            second_edition_label: "Second Edition:".to_string(),
+            later_edition_label: "Edition {n}:".to_string(),
+            permission_requests: "For permission requests, write to:".to_string(),
+            address_label: "Address:".to_string(),
+            email_label: "Email:".to_string(),
+            phone_label: "Phone:".to_string(),
+            attributions_label: "Attributions:".to_string(),
+            attributions: "Cover design includes 3D icons from vecteezy.com. Portions of this work were created with the assistance of an LLM.".to_string(),
+            disclaimer_label: "Disclaimer:".to_string(),
+            disclaimer: "The information contained in this manual is for educational purposes only. {school} makes every effort to ensure the accuracy of the content but accepts no responsibility for any errors or omissions.".to_string(),
+            instructions_title: "Instructions for Use".to_string(),
+            instructions: vec![
+                InstructionGroup {
+                    title: "For Teachers".to_string(),
+                    sections: vec![InstructionSection {
+                        title: None,
+                        items: to_strings(&[
+                            "Use the manual as your primary teaching guide during lessons",
+                            "Follow the lesson aims and objectives provided in each section",
+                            "Assign homework from any section based on your teaching progress",
+                            "Record homework assignments in pupils' homework communication books",
+                            "Monitor pupil progress through practice questions and activities",
+                            "Ensure pupils have necessary materials: pencils, pens, colours, rulers, etc.",
+                        ]),
+                    }],
+                },
+                InstructionGroup {
+                    title: "For Parents/Guardians".to_string(),
+                    sections: vec![InstructionSection {
+                        title: None,
+                        items: to_strings(&[
+                            "Provide a quiet, well-lit study space for homework",
+                            "Allow 15-20 minutes for homework sessions",
+                            "Help your child read instructions when needed",
+                            "Guide without giving direct answers",
+                            "Check and sign completed work in the homework communication book",
+                            "Contact school management if you have concerns about your child's progress",
+                        ]),
+                    }],
+                },
+                InstructionGroup {
+                    title: "For Pupils/Students".to_string(),
+                    sections: vec![
+                        InstructionSection {
+                            title: Some("At School:".to_string()),
+                            items: to_strings(&[
+                                "Listen carefully to your teacher's instructions",
+                                "Keep your manual clean and tidy",
+                                "Complete activities neatly",
+                                "Ask questions when you don't understand",
+                            ]),
+                        },
+                        InstructionSection {
+                            title: Some("At Home:".to_string()),
+                            items: to_strings(&[
+                                "Find a quiet place for homework",
+                                "Read questions slowly",
+                                "Write answers clearly",
+                                "Complete work assigned by your teacher",
+                                "Ask your parents for help if needed",
+                            ]),
+                        },
+                    ],
+                },
+            ],
+            table_of_contents: "Table of Contents".to_string(),
+            manual_label: "MANUAL".to_string(),
+            first_term: "FIRST TERM".to_string(),
+            second_term: "SECOND TERM".to_string(),
+            third_term: "THIRD TERM".to_string(),
+            ca_test: "CA TEST".to_string(),
+            topic_label: "TOPIC".to_string(),
+            lesson_objectives: "LESSON OBJECTIVES".to_string(),
+            previous_knowledge: "PUPILS' PREVIOUS KNOWLEDGE".to_string(),
+            instructional_materials: "INSTRUCTIONAL MATERIALS".to_string(),
+            teacher_preparation: "TEACHER PREPARATION".to_string(),
+            materials_label: "Materials:".to_string(),
+            duration_label: "Duration:".to_string(),
+            minutes: "mins".to_string(),
+            lesson_content: "LESSON CONTENT".to_string(),
+            conclusion: "CONCLUSION".to_string(),
+            key_points: "Key Points:".to_string(),
+            lesson_steps: "LESSON STEPS".to_string(),
+            step_label: "Step".to_string(),
+            phase_label: "Phase".to_string(),
+            teacher_actions: "Teacher Actions".to_string(),
+            pupil_activities: "Pupil Activities".to_string(),
+            formative_assessment: "FORMATIVE ASSESSMENT".to_string(),
+            summative_assessment: "SUMMATIVE ASSESSMENT".to_string(),
+            success_criteria: "SUCCESS CRITERIA".to_string(),
+            revision_questions: "REVISION QUESTIONS (Multiple Choice)".to_string(),
+            theoretical_questions: "THEORETICAL QUESTIONS".to_string(),
+            extension_activities: "EXTENSION ACTIVITIES".to_string(),
+            remediation: "REMEDIATION (Support for struggling learners)".to_string(),
+            references: "REFERENCES".to_string(),
+            class_activities: "CLASS ACTIVITIES".to_string(),
+            new_words: "NEW WORDS".to_string(),
+            glossary: "GLOSSARY".to_string(),
+            index: "INDEX".to_string(),
+            exam_questions_bank: "EXAM QUESTIONS BANK".to_string(),
+            answer_label: "Ans:".to_string(),
+            explanation_label: "Explanation:".to_string(),
+        }
+    }
+}
+
+fn to_strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
@@ -0,0 +1,432 @@
+// Turns LLM text into typst markup that always compiles and reads as plain text.
+// Every character typst would treat as syntax is backslash-escaped, so "₦500 * 2",
+// "info@school.ng" or "#1 rule" print as written. The only formatting a lesson can
+// opt into is **bold** and __italic__, which become #strong[..] and #emph[..], and
+// $...$ math, which is passed through as typst math once it parses and every name in
+// it is one typst math knows.
+// Templates render the result with eval(text, mode: "markup").
+
+use std::sync::LazyLock;
+
+use serde::Serialize;
+use serde_json::Value;
+use typst::foundations::Module;
+use typst::syntax::{parse_math, SyntaxKind, SyntaxNode};
+use typst::{Library, LibraryExt};
+
+use crate::AgentError;
+
+// Characters with a meaning in typst markup
+const MARKUP_CHARS: &[char] = &[
+    '\\', '#', '$', '*', '_', '@', '<', '>', '[', ']', '{', '}', '`', '~', '=', '-', '+', '/',
+];
+
+// Opt-in markup: delimiter in the lesson text and the typst function it maps to
+const SAFE_MARKUP: &[(&str, &str)] = &[("**", "strong"), ("__", "emph")];
+
+const MATH_DELIMITER: &str = "$";
+
+// Math may call layout functions like frac or sqrt, but nothing that reads files or runs code
+const DENIED_MATH_IDENTS: &[&str] = &[
+    "read", "eval", "json", "yaml", "toml", "csv", "xml", "cbor", "plugin", "image",
+];
+
+// What a name in math resolves to when the templates eval it: math functions and symbols
+static MATH_SCOPE: LazyLock<Module> = LazyLock::new(|| <Library as LibraryExt>::default().math);
+
+// A delimited span found in lesson text, with `function` empty for math
+struct Span<'a> {
+    start: usize,
+    end: usize,
+    inner: &'a str,
+    function: &'static str,
+}
+
+pub(crate) fn sanitize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(span) = next_span(rest) {
+        escape_into(&mut out, &rest[..span.start]);
+        if span.function.is_empty() {
+            if math_error(span.inner).is_none() {
+                out.push_str(MATH_DELIMITER);
+                out.push_str(span.inner);
+                out.push_str(MATH_DELIMITER);
+            } else {
+                // Lessons are validated when stored, but never let bad math fail a whole manual
+                escape_into(&mut out, &rest[span.start..span.end]);
+            }
+        } else {
+            out.push('#');
+            out.push_str(span.function);
+            out.push('[');
+            // Bold and italic may nest, e.g. "**all __three__ sides**"
+            out.push_str(&sanitize(span.inner));
+            out.push(']');
+        }
+        rest = &rest[span.end..];
+    }
+    escape_into(&mut out, rest);
+    out
+}
+
+pub(crate) fn sanitize_all(texts: Vec<String>) -> Vec<String> {
+    texts.iter().map(|t| sanitize(t)).collect()
+}
+
+// Check every $...$ span in a generated lesson (or translation) before it is stored
+pub(crate) fn validate_math<T: Serialize>(content: &T, topic: &str) -> Result<(), AgentError> {
+    let value = serde_json::to_value(content).map_err(|e| AgentError {
+        message: format!("Failed to serialize lesson '{}': {}", topic, e),
+        code: "SERIALIZATION_ERROR".to_string(),
+    })?;
+
+    let mut errors: Vec<String> = Vec::new();
+    collect_math_errors(&value, &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AgentError {
+            message: format!("Invalid math in lesson '{}': {}", topic, errors.join("; ")),
+            code: "INVALID_MATH".to_string(),
+        })
+    }
+}
+
+fn collect_math_errors(value: &Value, errors: &mut Vec<String>) {
+    match value {
+        Value::String(text) => {
+            let mut rest = text.as_str();
+            while let Some(span) = next_span(rest) {
+                if span.function.is_empty() {
+                    if let Some(error) = math_error(span.inner) {
+                        errors.push(format!("${}$: {}", span.inner, error));
+                    }
+                }
+                rest = &rest[span.end..];
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_math_errors(v, errors)),
+        Value::Object(fields) => fields.values().for_each(|v| collect_math_errors(v, errors)),
+        _ => {}
+    }
+}
+
+// Why a math span can't be rendered, if it can't
+fn math_error(math: &str) -> Option<String> {
+    if math.contains('#') {
+        return Some("code is not allowed in math".to_string());
+    }
+    let root = parse_math(math);
+    if root.erroneous() {
+        return root.errors().first().map(|e| e.message.to_string());
+    }
+    ident_error(&root)
+}
+
+// Multi-letter words parse as identifiers, so "$speed = d/t$" parses fine but fails to
+// compile with "unknown variable: speed". Quoted words, as in $"speed" = d/t$, are fine.
+fn ident_error(node: &SyntaxNode) -> Option<String> {
+    if node.kind() == SyntaxKind::MathIdent {
+        let ident = node.text().as_str();
+        if DENIED_MATH_IDENTS.contains(&ident) {
+            return Some(format!("'{}' is not allowed in math", ident));
+        }
+        if MATH_SCOPE.scope().get(ident).is_none() {
+            return Some(format!(
+                "unknown variable '{}' (quote words in math, e.g. \"{}\")",
+                ident, ident
+            ));
+        }
+    }
+    node.children().find_map(ident_error)
+}
+
+// Earliest well-formed span in the text. Like Markdown, "$5 and $10" is not math:
+// a span must not be empty, start or end with a space, or run across lines.
+fn next_span(text: &str) -> Option<Span<'_>> {
+    let delimiters = SAFE_MARKUP.iter().copied().chain([(MATH_DELIMITER, "")]);
+    let mut best: Option<Span> = None;
+    for (delimiter, function) in delimiters {
+        let mut from = 0;
+        while let Some(offset) = text[from..].find(delimiter) {
+            let start = from + offset;
+            if best.as_ref().is_some_and(|b| b.start <= start) {
+                break;
+            }
+            let inner_start = start + delimiter.len();
+            let Some(inner_len) = text[inner_start..].find(delimiter) else {
+                break;
+            };
+            let inner = &text[inner_start..inner_start + inner_len];
+            if !inner.is_empty() && inner.trim() == inner && !inner.contains('\n') {
+                best = Some(Span {
+                    start,
+                    end: inner_start + inner_len + delimiter.len(),
+                    inner,
+                    function,
+                });
+                break;
+            }
+            from = inner_start;
+        }
+    }
+    best
+}
+
+fn escape_into(out: &mut String, text: &str) {
+    let mut previous: Option<char> = None;
+    for c in text.chars() {
+        match c {
+            '\r' => continue,
+            // Keep the line break the LLM wrote instead of folding it into a space
+            '\n' => out.push_str("\\\n"),
+            // "1." at the start of a line would become a numbered list
+            '.' if previous.is_some_and(|p| p.is_ascii_digit()) => out.push_str("\\."),
+            c if MARKUP_CHARS.contains(&c) => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+        previous = Some(c);
+    }
+}
+
+// Back to the plain text a reader sees, e.g. for comparing or re-casing terms
+pub(crate) fn plain_text(markup: &str) -> String {
+    let mut out = String::with_capacity(markup.len());
+    let mut rest = markup;
+    while let Some(c) = rest.chars().next() {
+        if let Some(&(_, function)) = SAFE_MARKUP
+            .iter()
+            .find(|(_, function)| rest.starts_with(&format!("#{}[", function)))
+        {
+            rest = &rest[function.len() + 2..];
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\\' => {
+                if let Some(escaped) = rest.chars().next() {
+                    out.push(if escaped == '\n' { ' ' } else { escaped });
+                    rest = &rest[escaped.len_utf8()..];
+                }
+            }
+            // Only the opt-in spans leave an unescaped bracket or dollar sign
+            ']' | '$' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// The same markup as HTML, for exports that aren't rendered by typst. Escapes become
+// the literal (HTML-escaped) character, #strong[..] and #emph[..] become <strong> and
+// <em>, and math is kept as written in a span the page can style or typeset.
+pub(crate) fn markup_to_html(markup: &str) -> String {
+    let mut out = String::with_capacity(markup.len());
+    let mut open_tags: Vec<&str> = Vec::new();
+    let mut rest = markup;
+    while let Some(c) = rest.chars().next() {
+        if let Some(&(_, function)) = SAFE_MARKUP
+            .iter()
+            .find(|(_, function)| rest.starts_with(&format!("#{}[", function)))
+        {
+            let tag = if function == "strong" { "strong" } else { "em" };
+            out.push_str(&format!("<{}>", tag));
+            open_tags.push(tag);
+            rest = &rest[function.len() + 2..];
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\\' => {
+                if let Some(escaped) = rest.chars().next() {
+                    if escaped == '\n' {
+                        out.push_str("<br>");
+                    } else {
+                        escape_html_into(&mut out, escaped);
+                    }
+                    rest = &rest[escaped.len_utf8()..];
+                }
+            }
+            ']' => {
+                if let Some(tag) = open_tags.pop() {
+                    out.push_str(&format!("</{}>", tag));
+                }
+            }
+            '$' => {
+                let end = rest.find(MATH_DELIMITER).unwrap_or(rest.len());
+                out.push_str("<span class=\"math\">");
+                rest[..end]
+                    .chars()
+                    .for_each(|c| escape_html_into(&mut out, c));
+                out.push_str("</span>");
+                rest = &rest[(end + MATH_DELIMITER.len()).min(rest.len())..];
+            }
+            c => escape_html_into(&mut out, c),
+        }
+    }
+    out
+}
+
+// A piece of markup with the formatting it is shown in
+#[derive(Debug, PartialEq)]
+pub(crate) struct MarkupRun {
+    pub text: String,
+    pub strong: bool,
+    pub emph: bool,
+    pub math: bool,
+}
+
+// The same markup split into runs, for exports that style text themselves, e.g. DOCX.
+// Escapes become the literal character and line breaks stay "\n" inside the text.
+pub(crate) fn markup_runs(markup: &str) -> Vec<MarkupRun> {
+    let mut runs = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    let mut text = String::new();
+    let flush = |runs: &mut Vec<MarkupRun>, text: &mut String, open: &[&str], math: bool| {
+        if !text.is_empty() {
+            runs.push(MarkupRun {
+                text: std::mem::take(text),
+                strong: open.contains(&"strong"),
+                emph: open.contains(&"emph"),
+                math,
+            });
+        }
+    };
+    let mut rest = markup;
+    while let Some(c) = rest.chars().next() {
+        if let Some(&(_, function)) = SAFE_MARKUP
+            .iter()
+            .find(|(_, function)| rest.starts_with(&format!("#{}[", function)))
+        {
+            flush(&mut runs, &mut text, &open, false);
+            open.push(function);
+            rest = &rest[function.len() + 2..];
+            continue;
+        }
+        rest = &rest[c.len_utf8()..];
+        match c {
+            '\\' => {
+                if let Some(escaped) = rest.chars().next() {
+                    text.push(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+            }
+            ']' => {
+                flush(&mut runs, &mut text, &open, false);
+                open.pop();
+            }
+            '$' => {
+                flush(&mut runs, &mut text, &open, false);
+                let end = rest.find(MATH_DELIMITER).unwrap_or(rest.len());
+                text.push_str(&rest[..end]);
+                flush(&mut runs, &mut text, &open, true);
+                rest = &rest[(end + MATH_DELIMITER.len()).min(rest.len())..];
+            }
+            c => text.push(c),
+        }
+    }
+    flush(&mut runs, &mut text, &open, false);
+    runs
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    text.chars().for_each(|c| escape_html_into(&mut out, c));
+    out
+}
+
+fn escape_html_into(out: &mut String, c: char) {
+    match c {
+        '&' => out.push_str("&amp;"),
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#39;"),
+        c => out.push(c),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn escapes_typst_syntax() {
+        assert_eq!(sanitize("₦500 * 2"), "₦500 \\* 2");
+        assert_eq!(sanitize("#1 rule"), "\\#1 rule");
+        assert_eq!(sanitize("info@school.ng"), "info\\@school.ng");
+        assert_eq!(sanitize("a*b*c"), "a\\*b\\*c");
+    }
+
+    #[test]
+    fn numbers_at_line_start_are_not_lists() {
+        assert_eq!(sanitize("1. Add the tens"), "1\\. Add the tens");
+        assert_eq!(sanitize("Steps:\n2. Mix"), "Steps:\\\n2\\. Mix");
+    }
+
+    #[test]
+    fn money_is_not_math() {
+        assert_eq!(sanitize("$5 and $10"), "\\$5 and \\$10");
+        assert_eq!(markup_runs(&sanitize("$5 and $10")).len(), 1);
+    }
+
+    #[test]
+    fn bold_and_italic_nest() {
+        assert_eq!(
+            sanitize("**all __three__ sides**"),
+            "#strong[all #emph[three] sides]"
+        );
+        assert_eq!(
+            markup_to_html(&sanitize("**all __three__ sides**")),
+            "<strong>all <em>three</em> sides</strong>"
+        );
+    }
+
+    #[test]
+    fn runs_keep_formatting_and_line_breaks() {
+        let runs = markup_runs(&sanitize("**all __three__** of $x^2$\n₦5 * 2"));
+        let pieces: Vec<(&str, bool, bool, bool)> = runs
+            .iter()
+            .map(|r| (r.text.as_str(), r.strong, r.emph, r.math))
+            .collect();
+        assert_eq!(
+            pieces,
+            vec![
+                ("all ", true, false, false),
+                ("three", true, true, false),
+                (" of ", false, false, false),
+                ("x^2", false, false, true),
+                ("\n₦5 * 2", false, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn plain_text_undoes_sanitize() {
+        let text = "₦1,500 * 2 = 3.5 #strong[x] a_b <tag> {c} ~`/+-= info@school.ng costs $5";
+        assert_eq!(plain_text(&sanitize(text)), text);
+        assert_eq!(
+            plain_text(&sanitize("**Note:** add __all__ the $1/2$ parts")),
+            "Note: add all the 1/2 parts"
+        );
+    }
+
+    #[test]
+    fn math_names_must_be_known() {
+        assert!(math_error("2 times 3 = 6").is_none());
+        assert!(math_error("sqrt(16) = 4").is_none());
+        assert!(math_error("\"speed\" = \"distance\"/\"time\"").is_none());
+        assert!(math_error("speed = d/t").is_some_and(|e| e.contains("unknown variable 'speed'")));
+        assert!(math_error("read(\"x\")").is_some_and(|e| e.contains("not allowed")));
+
+        assert_eq!(sanitize("$speed = d/t$"), "\\$speed \\= d\\/t\\$");
+        assert_eq!(sanitize("$x^2$"), "$x^2$");
+        let err = validate_math(&json!({ "text": "$speed = d/t$" }), "Motion").unwrap_err();
+        assert_eq!(err.code, "INVALID_MATH");
+    }
+}
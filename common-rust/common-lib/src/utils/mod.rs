@@ -1,18 +1,104 @@
-use baml_client::apis::default_api::GenerateNigerianLessonError;
+use baml_client::apis::default_api::{GenerateNigerianLessonError, TranslateLessonContentError};
 use baml_client::apis::*;
-use baml_client::models::{ClassLevel, CompleteLessonContent, GenerateNigerianLessonRequest, Term};
-use serde_json::Value;
+use baml_client::models::{
+    ClassLevel, CompleteLessonContent, GenerateNigerianLessonRequest, Language, Term,
+    TranslateLessonContentRequest, TranslatedLessonContent,
+};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use wstd::http::body::IntoBody;
 use wstd::http::{Client, HeaderValue, Method, Request};
 
-use crate::{AgentError, TopicRecord};
+use crate::utils::markup::validate_math;
+use crate::utils::pdf_engine::term_to_string;
+use crate::utils::templates::ManualTemplate;
+use crate::{AgentError, SchoolBranding, TopicRecord};
 
+pub mod answer_sheet;
+pub mod cover;
+pub mod fonts;
+mod manual_strings;
+mod markup;
+pub mod pdf_cache;
 pub mod pdf_engine;
+pub mod scheme_of_work;
+pub mod templates;
+
+// The school whose topics and lessons an agent works with. Agents are named
+// "<tenant>:<name>", e.g. "greenfield:pdf-gen-Mathematics-Year 1"; names without a
+// tenant prefix belong to Johnethel, whose data predates multi-school support.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant {
+    id: String,
+}
+
+const DEFAULT_TENANT: &str = "johnethel";
+
+impl Tenant {
+    pub fn from_agent_name(agent_name: &str) -> Result<Tenant, AgentError> {
+        match agent_name.split_once(':') {
+            Some((id, _)) => Tenant::new(id),
+            None => Tenant::new(DEFAULT_TENANT),
+        }
+    }
+
+    // Tenant ids end up in the database name, so only lowercase letters, digits and hyphens
+    pub fn new(id: &str) -> Result<Tenant, AgentError> {
+        let id = id.trim().to_lowercase();
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(AgentError {
+                message: format!(
+                    "Invalid tenant id '{}', use letters, digits and hyphens",
+                    id
+                ),
+                code: "INVALID_TENANT".to_string(),
+            });
+        }
+        Ok(Tenant { id })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    // Every school gets its own database in the shared namespace
+    fn use_db(&self) -> String {
+        format!("USE NS main DB `{}-school-generated-lessons`;", self.id)
+    }
+}
+
+// Values from requests are bound as query parameters rather than pasted into the query,
+// so a quote in a class or subject can't end the string and run statements of its own.
+// Each LET adds a result to the response ahead of the statements that use it.
+fn bind(params: &[(&str, Value)]) -> String {
+    params
+        .iter()
+        .map(|(name, value)| format!("LET ${} = {};", name, value))
+        .collect()
+}
+
+// Record ids can't be bound as strings, so they are checked to be "table:key" instead
+fn validate_record_id(id: &str) -> Result<(), AgentError> {
+    let is_identifier =
+        |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    match id.split_once(':') {
+        Some((table, key)) if is_identifier(table) && is_identifier(key) => Ok(()),
+        _ => Err(AgentError {
+            message: format!("Invalid record id '{}'", id),
+            code: "INVALID_RECORD_ID".to_string(),
+        }),
+    }
+}
 
 pub async fn create_row(
+    tenant: &Tenant,
     input_row: CompleteLessonContent,
     source_id: String,
 ) -> Result<String, AgentError> {
+    validate_record_id(&source_id)?;
     let mut json_string = serde_json::to_value(&input_row).map_err(|e| AgentError {
         message: format!("Error converting rust struct to value: {:?}", e),
         code: "STRUCT_TO_VALUE_ERROR".to_string(),
@@ -33,14 +119,14 @@ pub async fn create_row(
 
     // SQL query
     let query = format!(
-        "USE NS main DB `johnethel-school-generated-lessons`; CREATE lesson_content CONTENT {};",
-        json_str
+        "{use_db} CREATE lesson_content CONTENT {};",
+        json_str,
+        use_db = tenant.use_db()
     );
 
     let response = db_request(query).await?;
-    let create_result = response.get(1).unwrap();
     // Check if query was successful
-    if let Some(status) = create_result.get("status") {
+    if let Some(status) = response.get(1).and_then(|r| r.get("status")) {
         if status != "OK" {
             return Err(AgentError {
                 message: format!("Query failed with status: {:?}", status),
@@ -56,87 +142,228 @@ pub async fn create_row(
     Ok(response)
 }
 
-pub async fn fetch_topics(table: &str) -> Result<Vec<TopicRecord>, AgentError> {
+// Topics table names arrive in request paths and are pasted into queries, so they
+// must be plain identifiers
+pub fn validate_table_name(table: &str) -> Result<(), AgentError> {
+    if table.is_empty() || !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(AgentError {
+            message: format!("Invalid table name '{}'", table),
+            code: "INVALID_TABLE_NAME".to_string(),
+        });
+    }
+    Ok(())
+}
+
+pub async fn fetch_topics(tenant: &Tenant, table: &str) -> Result<Vec<TopicRecord>, AgentError> {
+    validate_table_name(table)?;
     // SQL query
     let query = format!(
-        "USE NS main DB `johnethel-school-generated-lessons`; SELECT * FROM {};",
-        table
+        "{use_db} SELECT * FROM {};",
+        table,
+        use_db = tenant.use_db()
     );
-    let response = db_request(query).await?;
+    let records: Vec<TopicRecord> = select_records(query, table).await?;
+    println!("✓ Fetched {} records from {}", records.len(), table);
+    Ok(records)
+}
 
-    // Response structure:
-    // [0] = USE NS result (null)
-    // [1] = USE DB result (null)
-    // [2] = SELECT result (array of records)
+// Which of a subject's lessons to fetch: a term, a range of weeks and/or particular
+// lessons. The default fetches the whole year.
+#[derive(Debug, Clone, Default)]
+pub struct LessonFilter {
+    pub term: Option<Term>,
+    pub weeks: Option<(i32, i32)>, // inclusive
+    pub lesson_ids: Vec<String>,   // record ids, e.g. "lesson_content:abc123"
+}
 
-    let records = if let Some(select_result) = response.get(1) {
-        // Check if query was successful
-        if let Some(status) = select_result.get("status") {
-            if status != "OK" {
-                return Err(AgentError {
-                    message: format!("Query failed with status: {:?}", status),
-                    code: "QUERY_FAILED".to_string(),
-                });
-            }
-        }
+impl LessonFilter {
+    // Parse the filters as they arrive in requests, empty strings meaning no filter:
+    // term "first", "FIRST" or "1st Term", weeks "3" or "3-5", lesson ids comma separated
+    pub fn parse(term: &str, weeks: &str, lesson_ids: &str) -> Result<LessonFilter, AgentError> {
+        let term = match term.trim() {
+            "" => None,
+            term => Some(parse_term(term)?),
+        };
 
-        // Get the result array
-        match select_result.get("result") {
-            Some(Value::Array(arr)) => {
-                // Deserialize the array of records
-                serde_json::from_value(Value::Array(arr.clone())).map_err(|e| AgentError {
-                    message: format!("Failed to deserialize records: {:?}", e),
-                    code: "DESERIALIZE_ERROR".to_string(),
-                })?
+        let invalid_weeks = || AgentError {
+            message: format!("Invalid week range '{}', use e.g. 3 or 3-5", weeks),
+            code: "INVALID_WEEKS".to_string(),
+        };
+        let weeks = match weeks.trim() {
+            "" => None,
+            range => {
+                let (first, last) = range.split_once('-').unwrap_or((range, range));
+                let first: i32 = first.trim().parse().map_err(|_| invalid_weeks())?;
+                let last: i32 = last.trim().parse().map_err(|_| invalid_weeks())?;
+                // Weeks are numbered from 1
+                if first < 1 || first > last {
+                    return Err(invalid_weeks());
+                }
+                Some((first, last))
             }
-            Some(Value::Null) => {
-                // Table is empty or doesn't exist
-                println!("⚠️  Table '{}' is empty or doesn't exist", table);
-                Vec::new()
-            }
-            Some(other) => {
-                return Err(AgentError {
-                    message: format!("Unexpected result type: {:?}", other),
-                    code: "UNEXPECTED_RESULT".to_string(),
-                });
-            }
-            None => {
+        };
+
+        let mut ids = Vec::new();
+        for id in lesson_ids
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+        {
+            let key = id.strip_prefix("lesson_content:").unwrap_or(id);
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
                 return Err(AgentError {
-                    message: "No 'result' field in response".to_string(),
-                    code: "MISSING_RESULT".to_string(),
+                    message: format!("Invalid lesson id '{}'", id),
+                    code: "INVALID_LESSON_ID".to_string(),
                 });
             }
+            ids.push(format!("lesson_content:{}", key));
         }
-    } else {
-        return Err(AgentError {
-            message: format!(
-                "Expected at least 3 results, got {}",
-                response.len(),
-                // t.len()
-            ),
-            code: "INSUFFICIENT_RESULTS".to_string(),
-        });
-    };
-    println!("✓ Fetched {} records from {}", records.len(), table);
-    Ok(records)
+
+        Ok(LessonFilter {
+            term,
+            weeks,
+            lesson_ids: ids,
+        })
+    }
+
+    // CA test pages close a term, so they only belong in manuals of whole terms
+    pub fn whole_terms(&self) -> bool {
+        self.weeks.is_none() && self.lesson_ids.is_empty()
+    }
+
+    // Parameters for conditions(), bound ahead of the query
+    fn params(&self) -> Vec<(&'static str, Value)> {
+        match self.term {
+            Some(term) => vec![("term", json!(term_to_string(term)))],
+            None => Vec::new(),
+        }
+    }
+
+    fn conditions(&self) -> String {
+        let mut conditions = String::new();
+        if self.term.is_some() {
+            conditions.push_str(" AND term = $term");
+        }
+        if let Some((first, last)) = self.weeks {
+            conditions.push_str(&format!(" AND week >= {} AND week <= {}", first, last));
+        }
+        if !self.lesson_ids.is_empty() {
+            conditions.push_str(&format!(" AND id IN [{}]", self.lesson_ids.join(", ")));
+        }
+        conditions
+    }
 }
 
 pub async fn fetch_lessons(
+    tenant: &Tenant,
     subject: &str,
     class: &str,
+    filter: &LessonFilter,
 ) -> Result<Vec<CompleteLessonContent>, AgentError> {
     // SQL query
-    let query = format!("USE NS main DB `johnethel-school-generated-lessons`; SELECT * FROM lesson_content WHERE \"{}\" in class_level AND \"{}\" in subject ORDER BY term ASC, week ASC;", class, subject);
-    let response = db_request(query).await?;
+    let query = lessons_query(tenant, subject, class, filter);
+    let records: Vec<CompleteLessonContent> = select_records(query, "lesson_content").await?;
+    println!("✓ Fetched {} records from db", records.len());
+    Ok(records)
+}
+
+// Whether a stored subject is the one asked for, by the rule lessons_query applies in
+// SurrealQL: `$subject IN subject` on strings is a case-sensitive substring match
+pub(crate) fn subject_matches(stored: &str, requested: &str) -> bool {
+    stored.contains(requested)
+}
+
+fn lessons_query(tenant: &Tenant, subject: &str, class: &str, filter: &LessonFilter) -> String {
+    let mut params = vec![("class", json!(class)), ("subject", json!(subject))];
+    params.extend(filter.params());
+    format!(
+        "{use_db} {params} SELECT * FROM lesson_content WHERE $class IN class_level AND $subject IN subject{} ORDER BY term ASC, week ASC;",
+        filter.conditions(),
+        use_db = tenant.use_db(),
+        params = bind(&params)
+    )
+}
+
+// A stored lesson together with its record id, e.g. "lesson_content:abc123"
+#[derive(Debug, Clone, Deserialize)]
+pub struct LessonRecord {
+    pub id: String,
+    #[serde(flatten)]
+    pub content: CompleteLessonContent,
+}
 
-    // Response structure:
-    // [0] = USE NS result (null)
-    // [1] = USE DB result (null)
-    // [2] = SELECT result (array of records)
+pub async fn fetch_lesson_records(
+    tenant: &Tenant,
+    subject: &str,
+    class: &str,
+    filter: &LessonFilter,
+) -> Result<Vec<LessonRecord>, AgentError> {
+    // SQL query
+    let query = lessons_query(tenant, subject, class, filter);
+    let records: Vec<LessonRecord> = select_records(query, "lesson_content").await?;
+    println!("✓ Fetched {} records from db", records.len());
+    Ok(records)
+}
 
-    let records = if let Some(select_result) = response.get(1) {
-        // Check if query was successful
-        if let Some(status) = select_result.get("status") {
+// A stored translation together with the id of the English lesson it belongs to
+#[derive(Debug, Clone, Deserialize)]
+pub struct TranslationRecord {
+    pub lesson_id: String,
+    #[serde(flatten)]
+    pub content: TranslatedLessonContent,
+}
+
+pub async fn fetch_translations(
+    tenant: &Tenant,
+    lesson_ids: &[String],
+    language: Language,
+) -> Result<Vec<TranslationRecord>, AgentError> {
+    for id in lesson_ids {
+        validate_record_id(id)?;
+    }
+    // SQL query
+    let query = format!(
+        "{use_db} {params} SELECT * FROM lesson_translation WHERE language = $language AND lesson_id IN [{}];",
+        lesson_ids.join(", "),
+        use_db = tenant.use_db(),
+        params = bind(&[("language", json!(language_to_string(language)))])
+    );
+    let records: Vec<TranslationRecord> = select_records(query, "lesson_translation").await?;
+    println!("✓ Fetched {} translations from db", records.len());
+    Ok(records)
+}
+
+pub async fn create_translation_row(
+    tenant: &Tenant,
+    translated: TranslatedLessonContent,
+    lesson_id: String,
+    language: Language,
+) -> Result<String, AgentError> {
+    validate_record_id(&lesson_id)?;
+    let mut json_value = serde_json::to_value(&translated).map_err(|e| AgentError {
+        message: format!("Error converting rust struct to value: {:?}", e),
+        code: "STRUCT_TO_VALUE_ERROR".to_string(),
+    })?;
+    json_value["language"] = serde_json::json!(language_to_string(language));
+
+    let json_str = serde_json::to_string(&json_value).map_err(|e| AgentError {
+        message: format!("Error parsing json to string: {:?}", e),
+        code: "JSON_TO_STRING_PARSE_ERROR".to_string(),
+    })?;
+
+    // Replace any earlier translation so each lesson has one variant per language.
+    // lesson_id is set outside CONTENT so it is stored as a record link, not a string.
+    let query = format!(
+        "{use_db} {params} DELETE lesson_translation WHERE lesson_id = {id} AND language = $language; CREATE lesson_translation CONTENT {json} SET lesson_id = {id};",
+        id = lesson_id,
+        json = json_str,
+        use_db = tenant.use_db(),
+        params = bind(&[("language", json!(language_to_string(language)))])
+    );
+
+    let response = db_request(query).await?;
+    for result in response.iter().skip(1) {
+        if let Some(status) = result.get("status") {
             if status != "OK" {
                 return Err(AgentError {
                     message: format!("Query failed with status: {:?}", status),
@@ -144,46 +371,361 @@ pub async fn fetch_lessons(
                 });
             }
         }
+    }
+    let response = format!(
+        "Successfully translated lesson {} into {}",
+        lesson_id,
+        language_to_string(language)
+    );
+    println!("✓ {}", response);
+    Ok(response)
+}
 
-        // Get the result array
-        match select_result.get("result") {
-            Some(Value::Array(arr)) => {
-                // Deserialize the array of records
-                serde_json::from_value(Value::Array(arr.clone())).map_err(|e| AgentError {
-                    message: format!("Failed to deserialize records: {:?}", e),
-                    code: "DESERIALIZE_ERROR".to_string(),
-                })?
-            }
-            Some(Value::Null) => {
-                // Table is empty or doesn't exist
-                println!("⚠️  Table is empty or doesn't exist");
-                Vec::new()
-            }
-            Some(other) => {
+// An image in the lesson asset library, stored base64-encoded under the tag lessons use
+#[derive(Debug, Clone, Deserialize)]
+pub struct LessonAsset {
+    pub tag: String,
+    pub content_type: String,
+    pub data: String,
+}
+
+impl LessonAsset {
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        BASE64.decode(self.data.as_bytes()).ok()
+    }
+}
+
+// Tags are lowercase words joined by hyphens, e.g. "human-body"
+pub fn is_valid_asset_tag(tag: &str) -> bool {
+    !tag.is_empty()
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+// Image tags the lessons ask for, in order of first use
+pub fn lesson_image_tags(lessons: &[CompleteLessonContent]) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in lessons
+        .iter()
+        .flat_map(|l| l.content_sections.iter())
+        .filter_map(|cs| cs.image_tag.as_deref())
+    {
+        if is_valid_asset_tag(tag) && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+pub async fn fetch_assets(
+    tenant: &Tenant,
+    tags: &[String],
+) -> Result<Vec<LessonAsset>, AgentError> {
+    let tags: Vec<&String> = tags.iter().filter(|t| is_valid_asset_tag(t)).collect();
+    if tags.is_empty() {
+        return Ok(Vec::new());
+    }
+    // SQL query
+    let query = format!(
+        "{use_db} {params} SELECT tag, content_type, data FROM lesson_asset WHERE tag IN $tags;",
+        use_db = tenant.use_db(),
+        params = bind(&[("tags", json!(tags))])
+    );
+    let records: Vec<LessonAsset> = select_records(query, "lesson_asset").await?;
+    println!("✓ Fetched {} assets from db", records.len());
+    Ok(records)
+}
+
+pub async fn create_asset_row(
+    tenant: &Tenant,
+    tag: &str,
+    content_type: &str,
+    data: &[u8],
+) -> Result<String, AgentError> {
+    if !is_valid_asset_tag(tag) {
+        return Err(AgentError {
+            message: format!(
+                "Invalid asset tag '{}', use lowercase letters, digits and hyphens",
+                tag
+            ),
+            code: "INVALID_ASSET_TAG".to_string(),
+        });
+    }
+    if !content_type.starts_with("image/") {
+        return Err(AgentError {
+            message: format!("Unsupported asset content type '{}'", content_type),
+            code: "INVALID_ASSET_TYPE".to_string(),
+        });
+    }
+    let json_str = serde_json::to_string(&serde_json::json!({
+        "tag": tag,
+        "content_type": content_type,
+        "data": BASE64.encode(data),
+    }))
+    .map_err(|e| AgentError {
+        message: format!("Error parsing json to string: {:?}", e),
+        code: "JSON_TO_STRING_PARSE_ERROR".to_string(),
+    })?;
+
+    // Uploading the same tag again replaces the image
+    let query = format!(
+        "{use_db} {params} DELETE lesson_asset WHERE tag = $tag; CREATE lesson_asset CONTENT {json};",
+        json = json_str,
+        use_db = tenant.use_db(),
+        params = bind(&[("tag", json!(tag))])
+    );
+
+    let response = db_request(query).await?;
+    for result in response.iter().skip(1) {
+        if let Some(status) = result.get("status") {
+            if status != "OK" {
                 return Err(AgentError {
-                    message: format!("Unexpected result type: {:?}", other),
-                    code: "UNEXPECTED_RESULT".to_string(),
+                    message: format!("Query failed with status: {:?}", status),
+                    code: "QUERY_FAILED".to_string(),
                 });
             }
-            None => {
+        }
+    }
+    let response = format!("Successfully stored asset {} ({} bytes)", tag, data.len());
+    println!("✓ {}", response);
+    Ok(response)
+}
+
+// A template version in the registry, without its source
+#[derive(Debug, Clone, Deserialize)]
+pub struct TemplateVersion {
+    pub name: String,
+    pub version: u32,
+}
+
+// The newest version of a registered template, or the given version
+pub async fn fetch_template(
+    tenant: &Tenant,
+    name: &str,
+    version: Option<u32>,
+) -> Result<Option<ManualTemplate>, AgentError> {
+    if !is_valid_asset_tag(name) {
+        return Ok(None);
+    }
+    let version_filter = match version {
+        Some(version) => format!(" AND version = {}", version),
+        None => String::new(),
+    };
+    // SQL query
+    let query = format!(
+        "{use_db} {params} SELECT name, version, source FROM manual_template WHERE name = $name{} ORDER BY version DESC LIMIT 1;",
+        version_filter,
+        use_db = tenant.use_db(),
+        params = bind(&[("name", json!(name))])
+    );
+    let records: Vec<ManualTemplate> = select_records(query, "manual_template").await?;
+    Ok(records.into_iter().next())
+}
+
+pub async fn fetch_template_versions(tenant: &Tenant) -> Result<Vec<TemplateVersion>, AgentError> {
+    // SQL query
+    let query = format!(
+        "{use_db} SELECT name, version FROM manual_template ORDER BY name, version;",
+        use_db = tenant.use_db()
+    );
+    select_records(query, "manual_template").await
+}
+
+// Tries at storing a template when concurrent uploads of the same name collide
+const TEMPLATE_UPLOAD_ATTEMPTS: usize = 3;
+
+// Store a template as the next version of its name, returning that version. The version
+// is picked and the row created in one transaction, and the unique index on
+// (name, version) fails an upload that raced another to the same version, which is
+// then retried with the next one.
+pub async fn create_template_row(
+    tenant: &Tenant,
+    name: &str,
+    source: &str,
+) -> Result<u32, AgentError> {
+    let query = format!(
+        "{use_db} DEFINE INDEX IF NOT EXISTS manual_template_version ON TABLE manual_template FIELDS name, version UNIQUE; \
+         {params} BEGIN TRANSACTION; \
+         LET $version = (math::max((SELECT VALUE version FROM manual_template WHERE name = $name)) ?? 0) + 1; \
+         CREATE manual_template CONTENT {{ name: $name, version: $version, source: $source }} RETURN version; \
+         COMMIT TRANSACTION;",
+        use_db = tenant.use_db(),
+        params = bind(&[("name", json!(name)), ("source", json!(source))])
+    );
+
+    let mut attempt = 1;
+    loop {
+        let result = db_request(query.clone())
+            .await
+            .and_then(|response| created_version(&response));
+        match result {
+            Err(err) if attempt < TEMPLATE_UPLOAD_ATTEMPTS => {
+                println!(
+                    "⚠️  Storing template '{}' failed, retrying: {}",
+                    name, err.message
+                );
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+// The version create_template_row's CREATE returned. A failed statement fails the
+// whole transaction, so any error status means nothing was stored.
+fn created_version(response: &[Value]) -> Result<u32, AgentError> {
+    if let Some(failed) = response
+        .iter()
+        .find(|r| r.get("status").is_some_and(|s| s != "OK"))
+    {
+        return Err(AgentError {
+            message: format!("Query failed: {}", failed),
+            code: "QUERY_FAILED".to_string(),
+        });
+    }
+    response
+        .iter()
+        .rev()
+        .find_map(|r| r.get("result")?.get(0)?.get("version")?.as_u64())
+        .and_then(|version| u32::try_from(version).ok())
+        .ok_or_else(|| AgentError {
+            message: "Template was stored without a version".to_string(),
+            code: "QUERY_FAILED".to_string(),
+        })
+}
+
+// The school's branding profile, or the Johnethel defaults when none is stored
+pub async fn fetch_branding(tenant: &Tenant) -> Result<SchoolBranding, AgentError> {
+    // SQL query
+    let query = format!(
+        "{use_db} SELECT * OMIT id FROM school_branding:profile;",
+        use_db = tenant.use_db()
+    );
+    let records: Vec<SchoolBranding> = select_records(query, "school_branding").await?;
+    Ok(records.into_iter().next().unwrap_or_default())
+}
+
+pub async fn save_branding(
+    tenant: &Tenant,
+    branding: SchoolBranding,
+) -> Result<String, AgentError> {
+    validate_branding(&branding)?;
+    let json_str = serde_json::to_string(&branding).map_err(|e| AgentError {
+        message: format!("Error parsing json to string: {:?}", e),
+        code: "JSON_TO_STRING_PARSE_ERROR".to_string(),
+    })?;
+
+    // A single profile record per school database
+    let query = format!(
+        "{use_db} DELETE school_branding:profile; CREATE school_branding:profile CONTENT {};",
+        json_str,
+        use_db = tenant.use_db()
+    );
+
+    let response = db_request(query).await?;
+    for result in response.iter().skip(1) {
+        if let Some(status) = result.get("status") {
+            if status != "OK" {
                 return Err(AgentError {
-                    message: "No 'result' field in response".to_string(),
-                    code: "MISSING_RESULT".to_string(),
+                    message: format!("Query failed with status: {:?}", status),
+                    code: "QUERY_FAILED".to_string(),
                 });
             }
         }
-    } else {
+    }
+    let response = format!("Successfully saved branding for {}", branding.name);
+    println!("✓ {}", response);
+    Ok(response)
+}
+
+fn validate_branding(branding: &SchoolBranding) -> Result<(), AgentError> {
+    let invalid = |message: String| AgentError {
+        message,
+        code: "INVALID_BRANDING".to_string(),
+    };
+    if branding.name.trim().is_empty() || branding.short_name.trim().is_empty() {
+        return Err(invalid(
+            "School name and short name are required".to_string(),
+        ));
+    }
+    for colour in [&branding.primary_colour, &branding.accent_colour]
+        .into_iter()
+        .chain(branding.subject_covers.iter().map(|c| &c.colour))
+    {
+        if !is_hex_colour(colour) {
+            return Err(invalid(format!("Invalid colour '{}', use #rrggbb", colour)));
+        }
+    }
+    for tag in [&branding.logo_tag, &branding.watermark_tag]
+        .into_iter()
+        .chain(branding.subject_covers.iter().map(|c| &c.artwork_tag))
+        .flatten()
+    {
+        if !is_valid_asset_tag(tag) {
+            return Err(invalid(format!("Invalid asset tag '{}'", tag)));
+        }
+    }
+    Ok(())
+}
+
+pub fn is_hex_colour(colour: &str) -> bool {
+    colour.len() == 7
+        && colour.starts_with('#')
+        && colour[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Run a SELECT and deserialize the records it returns.
+// Response structure:
+// [0] = USE NS/DB result (null)
+// then one (null) result per bound parameter
+// [last] = SELECT result (array of records)
+async fn select_records<T: DeserializeOwned>(
+    query: String,
+    table: &str,
+) -> Result<Vec<T>, AgentError> {
+    let response = db_request(query).await?;
+
+    let Some(select_result) = response.last().filter(|_| response.len() >= 2) else {
         return Err(AgentError {
-            message: format!(
-                "Expected at least 3 results, got {}",
-                response.len(),
-                // t.len()
-            ),
+            message: format!("Expected at least 2 results, got {}", response.len()),
             code: "INSUFFICIENT_RESULTS".to_string(),
         });
     };
-    println!("✓ Fetched {} records from db", records.len());
-    Ok(records)
+
+    // Check if query was successful
+    if let Some(status) = select_result.get("status") {
+        if status != "OK" {
+            return Err(AgentError {
+                message: format!("Query failed with status: {:?}", status),
+                code: "QUERY_FAILED".to_string(),
+            });
+        }
+    }
+
+    // Get the result array
+    match select_result.get("result") {
+        Some(Value::Array(arr)) => {
+            // Deserialize the array of records
+            serde_json::from_value(Value::Array(arr.clone())).map_err(|e| AgentError {
+                message: format!("Failed to deserialize records: {:?}", e),
+                code: "DESERIALIZE_ERROR".to_string(),
+            })
+        }
+        Some(Value::Null) => {
+            // Table is empty or doesn't exist
+            println!("⚠️  Table '{}' is empty or doesn't exist", table);
+            Ok(Vec::new())
+        }
+        Some(other) => Err(AgentError {
+            message: format!("Unexpected result type: {:?}", other),
+            code: "UNEXPECTED_RESULT".to_string(),
+        }),
+        None => Err(AgentError {
+            message: "No 'result' field in response".to_string(),
+            code: "MISSING_RESULT".to_string(),
+        }),
+    }
 }
 
 async fn db_request(query: String) -> Result<Vec<Value>, AgentError> {
@@ -245,11 +787,13 @@ pub async fn generate_lesson_with_baml(
                 message: format!("Failed to generate content: {:?}", e),
                 code: "GENERATION_ERROR".to_string(),
             })?;
+    validate_math(&resp, &resp.topic_title)?;
     Ok(resp)
 }
 
-fn convert_from_topic_record_to_baml_format(input: TopicRecord) -> GenerateNigerianLessonRequest {
-    let class_level = match input.class.as_str() {
+// Convert the class names used in the topics tables to the BAML class level
+pub fn topic_class_level(class: &str) -> Result<ClassLevel, AgentError> {
+    match class {
         "Year 1" => Ok(ClassLevel::Primary1),
         "Year 2" => Ok(ClassLevel::Primary2),
         "Year 3" => Ok(ClassLevel::Primary3),
@@ -259,20 +803,39 @@ fn convert_from_topic_record_to_baml_format(input: TopicRecord) -> GenerateNiger
         "Jss 2" => Ok(ClassLevel::Jss2),
         "Jss 3" => Ok(ClassLevel::Jss3),
         _ => Err(AgentError {
-            message: format!("Invalid class level: {}", input.class),
+            message: format!("Invalid class level: {}", class),
             code: "INVALID_CLASS_LEVEL".to_string(),
         }),
-    };
+    }
+}
 
-    let term = match input.term.as_str() {
+// Convert the term names used in the topics tables to the BAML term
+pub fn topic_term(term: &str) -> Result<Term, AgentError> {
+    match term {
         "1st Term" | "Noel Term" => Ok(Term::First),
         "2nd Term" | "Calvary Term" => Ok(Term::Second),
         "3rd Term" | "Summer Term" => Ok(Term::Third),
         _ => Err(AgentError {
-            message: format!("Invalid term: {}", input.term),
+            message: format!("Invalid term: {}", term),
             code: "INVALID_TERM".to_string(),
         }),
-    };
+    }
+}
+
+// Convert a term as it arrives in requests: "first", "FIRST", "1" or a topics table name
+pub fn parse_term(term: &str) -> Result<Term, AgentError> {
+    let term = term.trim();
+    match term.to_uppercase().as_str() {
+        "FIRST" | "1" => Ok(Term::First),
+        "SECOND" | "2" => Ok(Term::Second),
+        "THIRD" | "3" => Ok(Term::Third),
+        _ => topic_term(term),
+    }
+}
+
+fn convert_from_topic_record_to_baml_format(input: TopicRecord) -> GenerateNigerianLessonRequest {
+    let class_level = topic_class_level(input.class.as_str());
+    let term = topic_term(input.term.as_str());
     println!("Conversion to baml type complete");
     GenerateNigerianLessonRequest {
         age_group: input.agegroup,
@@ -290,12 +853,164 @@ async fn generate_nigerian_lesson(
     configuration: &configuration::Configuration,
     generate_nigerian_lesson_request: GenerateNigerianLessonRequest,
 ) -> Result<CompleteLessonContent, Error<GenerateNigerianLessonError>> {
-    let p_body_generate_nigerian_lesson_request = generate_nigerian_lesson_request;
-    let uri_str = format!("{}/call/GenerateNigerianLesson", configuration.base_path);
+    call_baml_function(
+        configuration,
+        "GenerateNigerianLesson",
+        &generate_nigerian_lesson_request,
+    )
+    .await
+}
+
+pub async fn translate_lesson_with_baml(
+    lesson: CompleteLessonContent,
+    language: Language,
+) -> Result<TranslatedLessonContent, AgentError> {
+    let config = baml_client::apis::configuration::Configuration::default();
+    let request = TranslateLessonContentRequest {
+        lesson: lesson.clone(),
+        language,
+        __baml_options__: None,
+    };
+    let resp = translate_lesson_content(&config, request)
+        .await
+        .map_err(|e| AgentError {
+            message: format!("Failed to translate content: {:?}", e),
+            code: "TRANSLATION_ERROR".to_string(),
+        })?;
+    validate_math(&resp, &resp.topic_title)?;
+    check_translation_counts(&lesson, &resp)?;
+    Ok(resp)
+}
+
+// Translations are paired with the lesson item by item, so every list has to have the
+// same length as the English one or items would be dropped or mismatched
+fn check_translation_counts(
+    lesson: &CompleteLessonContent,
+    translated: &TranslatedLessonContent,
+) -> Result<(), AgentError> {
+    let mut mismatches: Vec<String> = Vec::new();
+    let mut compare = |list: String, english: usize, translation: usize| {
+        if english != translation {
+            mismatches.push(format!(
+                "{} ({} in English, {} translated)",
+                list, english, translation
+            ));
+        }
+    };
+    compare(
+        "objectives".to_string(),
+        lesson.objectives.len(),
+        translated.objectives.len(),
+    );
+    compare(
+        "content sections".to_string(),
+        lesson.content_sections.len(),
+        translated.content_sections.len(),
+    );
+    for (section, ts) in lesson
+        .content_sections
+        .iter()
+        .zip(&translated.content_sections)
+    {
+        compare(
+            format!("sub-points of section {}", section.section_number),
+            section.sub_points.as_ref().map_or(0, Vec::len),
+            ts.sub_points.len(),
+        );
+    }
+    compare(
+        "key points".to_string(),
+        lesson.key_points.len(),
+        translated.key_points.len(),
+    );
+    // Translations stored before glossaries were translated have none
+    if let Some(glossary) = &translated.glossary {
+        compare(
+            "glossary".to_string(),
+            lesson.glossary.as_ref().map_or(0, Vec::len),
+            glossary.len(),
+        );
+    }
+    compare(
+        "multiple choice questions".to_string(),
+        lesson.mcq_questions.len(),
+        translated.mcq_questions.len(),
+    );
+    compare(
+        "theory questions".to_string(),
+        lesson.theoretical_questions.len(),
+        translated.theoretical_questions.len(),
+    );
+    for (i, (q, tq)) in lesson
+        .theoretical_questions
+        .iter()
+        .zip(&translated.theoretical_questions)
+        .enumerate()
+    {
+        compare(
+            format!("parts of theory question {}", i + 1),
+            q.parts.len(),
+            tq.parts.len(),
+        );
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(AgentError {
+            message: format!(
+                "Translation of '{}' doesn't match the lesson: {}",
+                lesson.topic_title,
+                mismatches.join(", ")
+            ),
+            code: "TRANSLATION_MISMATCH".to_string(),
+        })
+    }
+}
+
+async fn translate_lesson_content(
+    configuration: &configuration::Configuration,
+    translate_lesson_content_request: TranslateLessonContentRequest,
+) -> Result<TranslatedLessonContent, Error<TranslateLessonContentError>> {
+    call_baml_function(
+        configuration,
+        "TranslateLessonContent",
+        &translate_lesson_content_request,
+    )
+    .await
+}
+
+// Accepts "yoruba"/"yo", "hausa"/"ha" and "igbo"/"ig"
+pub fn parse_language(language: &str) -> Result<Language, AgentError> {
+    match language.to_lowercase().as_str() {
+        "yoruba" | "yo" => Ok(Language::Yoruba),
+        "hausa" | "ha" => Ok(Language::Hausa),
+        "igbo" | "ig" => Ok(Language::Igbo),
+        _ => Err(AgentError {
+            message: format!("Unsupported language: {}", language),
+            code: "INVALID_LANGUAGE".to_string(),
+        }),
+    }
+}
+
+pub fn language_to_string(language: Language) -> String {
+    match language {
+        Language::Yoruba => "YORUBA".to_string(),
+        Language::Hausa => "HAUSA".to_string(),
+        Language::Igbo => "IGBO".to_string(),
+    }
+}
+
+// POST a request body to a BAML function using the wstd HTTP client available in the component
+async fn call_baml_function<B: Serialize, R: DeserializeOwned, E>(
+    configuration: &configuration::Configuration,
+    function_name: &str,
+    body: &B,
+) -> Result<R, Error<E>> {
+    let uri_str = format!("{}/call/{}", configuration.base_path, function_name);
 
     // Serialize the request body to JSON
-    let body_json = serde_json::to_string(&p_body_generate_nigerian_lesson_request)
-        .map_err(|e| Error::from(e))?;
+    let body_json = serde_json::to_string(body).map_err(|e| Error::from(e))?;
 
     // Build the request
     let mut req_builder = Request::builder()
@@ -335,9 +1050,191 @@ async fn generate_nigerian_lesson(
         )));
     }
     let mut body = response.into_body();
-    let response_json: CompleteLessonContent = body
+    let response_json: R = body
         .json()
         .await
         .map_err(|e| Error::from(std::io::Error::new(std::io::ErrorKind::Other, e)))?;
     Ok(response_json)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn created_version_reads_the_create_result() {
+        let response = vec![
+            json!({ "status": "OK", "result": null }),
+            json!({ "status": "OK", "result": null }),
+            json!({ "status": "OK", "result": [{ "version": 4 }] }),
+        ];
+        assert_eq!(created_version(&response).unwrap(), 4);
+
+        // A concurrent upload took the version, the unique index fails the transaction
+        let response = vec![
+            json!({ "status": "OK", "result": null }),
+            json!({ "status": "ERR", "result": "Database index `manual_template_version` already contains ['x', 4]" }),
+        ];
+        assert_eq!(created_version(&response).unwrap_err().code, "QUERY_FAILED");
+    }
+
+    #[test]
+    fn empty_filter_fetches_the_whole_year() {
+        let filter = LessonFilter::parse("", " ", "").unwrap();
+        assert!(filter.term.is_none() && filter.weeks.is_none() && filter.lesson_ids.is_empty());
+        assert!(filter.whole_terms());
+        assert_eq!(filter.conditions(), "");
+        assert!(filter.params().is_empty());
+    }
+
+    #[test]
+    fn filter_parses_terms_and_week_ranges() {
+        let filter = LessonFilter::parse("first", "3-5", "").unwrap();
+        assert!(matches!(filter.term, Some(Term::First)));
+        assert_eq!(filter.weeks, Some((3, 5)));
+        assert!(!filter.whole_terms());
+        // The term is bound, only the parsed week numbers are inlined
+        assert_eq!(
+            filter.conditions(),
+            " AND term = $term AND week >= 3 AND week <= 5"
+        );
+        assert_eq!(filter.params(), vec![("term", json!("FIRST"))]);
+
+        let filter = LessonFilter::parse("2nd Term", " 4 ", "").unwrap();
+        assert!(matches!(filter.term, Some(Term::Second)));
+        assert_eq!(filter.weeks, Some((4, 4)));
+        let filter = LessonFilter::parse("3", "", "").unwrap();
+        assert!(matches!(filter.term, Some(Term::Third)));
+    }
+
+    #[test]
+    fn filter_rejects_bad_terms_and_weeks() {
+        for weeks in [
+            "5-3",
+            "0",
+            "0-2",
+            "-3",
+            "three",
+            "3-",
+            "3-5-7",
+            "3;DELETE lesson_content",
+        ] {
+            let err = LessonFilter::parse("", weeks, "").unwrap_err();
+            assert_eq!(err.code, "INVALID_WEEKS", "{}", weeks);
+        }
+        let err = LessonFilter::parse("fourth", "", "").unwrap_err();
+        assert_eq!(err.code, "INVALID_TERM");
+    }
+
+    #[test]
+    fn filter_keeps_well_formed_lesson_ids_only() {
+        let filter = LessonFilter::parse("", "", "abc123, lesson_content:def_4,").unwrap();
+        assert_eq!(
+            filter.lesson_ids,
+            vec!["lesson_content:abc123", "lesson_content:def_4"]
+        );
+        assert_eq!(
+            filter.conditions(),
+            " AND id IN [lesson_content:abc123, lesson_content:def_4]"
+        );
+        for ids in [
+            "lesson_content:",
+            "abc-1",
+            "topics:abc",
+            "abc]; DELETE lesson_content; --",
+            "abc, x\"y",
+        ] {
+            let err = LessonFilter::parse("", "", ids).unwrap_err();
+            assert_eq!(err.code, "INVALID_LESSON_ID", "{}", ids);
+        }
+    }
+
+    #[test]
+    fn record_ids_and_table_names_must_be_identifiers() {
+        assert!(validate_record_id("lesson_content:abc_123").is_ok());
+        for id in [
+            "",
+            "lesson_content",
+            "lesson_content:",
+            ":abc",
+            "a:b:c",
+            "lesson_content:abc'",
+            "lesson_content:abc\"",
+            "lesson_content:abc;DELETE lesson_content",
+            "lesson_content:abc; DELETE lesson_content;",
+        ] {
+            assert_eq!(
+                validate_record_id(id).unwrap_err().code,
+                "INVALID_RECORD_ID",
+                "{}",
+                id
+            );
+        }
+
+        assert!(validate_table_name("primary_1_topics").is_ok());
+        for table in [
+            "",
+            "topics;DELETE lesson_content",
+            "topics'",
+            "topics`",
+            "a-b",
+            "a b",
+        ] {
+            let err = validate_table_name(table).unwrap_err();
+            assert_eq!(err.code, "INVALID_TABLE_NAME", "{}", table);
+        }
+    }
+
+    #[test]
+    fn request_values_are_bound_not_pasted() {
+        let payload = "PRIMARY_1\"; DELETE lesson_content; --";
+        assert_eq!(
+            bind(&[("class", json!(payload)), ("week", json!(3))]),
+            r#"LET $class = "PRIMARY_1\"; DELETE lesson_content; --";LET $week = 3;"#
+        );
+
+        let tenant = Tenant::new("greenfield").unwrap();
+        let query = lessons_query(
+            &tenant,
+            "Maths' OR true; --",
+            payload,
+            &LessonFilter::default(),
+        );
+        assert!(query.contains(r#"LET $class = "PRIMARY_1\"; DELETE lesson_content; --";"#));
+        assert!(query.contains(r#"LET $subject = "Maths' OR true; --";"#));
+        // The payload only appears inside its JSON string literal
+        assert_eq!(query.matches("DELETE").count(), 1);
+        assert_eq!(query.matches("OR true").count(), 1);
+        assert!(query.contains("WHERE $class IN class_level AND $subject IN subject ORDER BY"));
+    }
+
+    #[test]
+    fn tenants_come_from_the_agent_name_prefix() {
+        let tenant = Tenant::from_agent_name("greenfield:pdf-gen-Mathematics-Year 1").unwrap();
+        assert_eq!(tenant.id(), "greenfield");
+        assert_eq!(
+            tenant.use_db(),
+            "USE NS main DB `greenfield-school-generated-lessons`;"
+        );
+        let tenant = Tenant::from_agent_name(" Green-Field2 :pdf-gen").unwrap();
+        assert_eq!(tenant.id(), "green-field2");
+        // Names without a prefix keep using Johnethel's data
+        let tenant = Tenant::from_agent_name("pdf-gen-Mathematics-Year 1").unwrap();
+        assert_eq!(tenant.id(), "johnethel");
+    }
+
+    #[test]
+    fn tenants_with_other_characters_are_rejected() {
+        for name in [
+            ":pdf-gen",
+            "green field:pdf-gen",
+            "green_field:pdf-gen",
+            "greenfield;DELETE:pdf-gen",
+            "green`field:pdf-gen",
+            "grëenfield:pdf-gen",
+        ] {
+            let err = Tenant::from_agent_name(name).unwrap_err();
+            assert_eq!(err.code, "INVALID_TENANT", "{}", name);
+        }
+    }
+}
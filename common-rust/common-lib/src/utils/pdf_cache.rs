@@ -0,0 +1,157 @@
+// Manuals are cached by a hash of everything that goes into them: the lessons and
+// translation as stored, the template version and source, the uploaded and shipped
+// images, the fonts, the branding and the request options. Editing a lesson changes the hash, so stale entries are
+// never served, and the hash doubles as the HTTP ETag.
+
+use baml_client::models::CompleteLessonContent;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::utils::fonts::fonts_fingerprint;
+use crate::utils::pdf_engine::{library_image, ManualTranslation};
+use crate::utils::templates::ManualTemplate;
+use crate::utils::{is_valid_asset_tag, language_to_string, lesson_image_tags, LessonAsset};
+use crate::{AgentError, SchoolBranding};
+
+// A year's manual is a few MB and an agent serves one subject and class, so a few
+// entries cover the pupil, teacher and translated variants
+const MAX_CACHED_MANUALS: usize = 6;
+
+#[derive(Default)]
+pub struct ManualCache {
+    entries: Vec<(String, Vec<u8>)>, // most recently used last
+}
+
+impl ManualCache {
+    pub fn get(&mut self, key: &str) -> Option<Vec<u8>> {
+        let position = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(position);
+        let pdf = entry.1.clone();
+        self.entries.push(entry);
+        Some(pdf)
+    }
+
+    pub fn insert(&mut self, key: String, pdf: Vec<u8>) {
+        self.entries.retain(|(k, _)| *k != key);
+        if self.entries.len() == MAX_CACHED_MANUALS {
+            self.entries.remove(0);
+        }
+        self.entries.push((key, pdf));
+    }
+}
+
+// Request options for a manual, shared by the engines and the cache key
+#[derive(Clone, Copy)]
+pub struct ManualOptions<'a> {
+    pub subject_name: &'a str,
+    pub class_year: &'a str,
+    pub mode: &'a str, // "pupil", "teacher" or "lesson-note"
+    pub question_bank: bool,
+    pub ca_tests: bool, // false for subsets of a term, which shouldn't end in CA pages
+}
+
+pub fn manual_cache_key(
+    lessons: &[CompleteLessonContent],
+    translation: Option<&ManualTranslation>,
+    template: &ManualTemplate,
+    assets: &[LessonAsset],
+    branding: &SchoolBranding,
+    options: &ManualOptions,
+) -> Result<String, AgentError> {
+    let translation = translation.map(|t| {
+        json!({
+            "language": language_to_string(t.language),
+            "bilingual": t.bilingual,
+            "lessons": t.lessons,
+        })
+    });
+    let assets: Vec<_> = assets
+        .iter()
+        .map(|a| json!([a.tag, a.content_type, a.data]))
+        .collect();
+    // Shipped images only change with a deploy, so their hash is enough
+    let mut tags = lesson_image_tags(lessons);
+    tags.extend(
+        branding
+            .logo_tag
+            .iter()
+            .chain(&branding.watermark_tag)
+            .cloned(),
+    );
+    let library: Vec<_> = tags
+        .iter()
+        .filter(|tag| is_valid_asset_tag(tag))
+        .map(|tag| {
+            let hash = library_image(tag).map(|(data, _)| format!("{:x}", Sha256::digest(data)));
+            json!([tag, hash])
+        })
+        .collect();
+    let inputs = json!({
+        "lessons": lessons,
+        "translation": translation,
+        "template": [template.name, template.version, template.source],
+        "assets": assets,
+        "library": library,
+        "fonts": fonts_fingerprint(),
+        "branding": branding,
+        "options": [
+            options.subject_name,
+            options.class_year,
+            options.mode,
+            options.question_bank,
+            options.ca_tests,
+        ],
+    });
+    let bytes = serde_json::to_vec(&inputs).map_err(|e| AgentError {
+        message: format!("Failed to serialize manual inputs: {:?}", e),
+        code: "SERIALIZATION_ERROR".to_string(),
+    })?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+// Whether an If-None-Match header value names this ETag, e.g. `"abc"`, `W/"abc", "def"` or `*`
+pub fn etag_matches(if_none_match: &str, key: &str) -> bool {
+    if_none_match.split(',').any(|tag| {
+        let tag = tag.trim();
+        tag == "*" || tag.trim_start_matches("W/").trim_matches('"') == key
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etag_matches_quoted_weak_listed_and_any() {
+        assert!(etag_matches("\"abc\"", "abc"));
+        assert!(etag_matches("W/\"abc\"", "abc"));
+        assert!(etag_matches("\"def\", W/\"abc\"", "abc"));
+        assert!(etag_matches("*", "abc"));
+        assert!(!etag_matches("\"def\"", "abc"));
+        assert!(!etag_matches("\"abcd\"", "abc"));
+        assert!(!etag_matches("", "abc"));
+    }
+
+    #[test]
+    fn cache_evicts_the_least_recently_used() {
+        let mut cache = ManualCache::default();
+        for i in 0..MAX_CACHED_MANUALS {
+            cache.insert(format!("key{}", i), vec![i as u8]);
+        }
+        // Reading key0 makes key1 the oldest
+        assert_eq!(cache.get("key0"), Some(vec![0]));
+        cache.insert("new".to_string(), vec![99]);
+        assert_eq!(cache.get("key1"), None);
+        assert_eq!(cache.get("key0"), Some(vec![0]));
+        assert_eq!(cache.get("new"), Some(vec![99]));
+    }
+
+    #[test]
+    fn cache_replaces_an_existing_key() {
+        let mut cache = ManualCache::default();
+        cache.insert("key".to_string(), vec![1]);
+        cache.insert("key".to_string(), vec![2]);
+        assert_eq!(cache.get("key"), Some(vec![2]));
+        assert_eq!(cache.entries.len(), 1);
+    }
+}
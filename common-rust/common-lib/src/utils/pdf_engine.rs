@@ -1,93 +1,253 @@
-use baml_client::models::{ClassLevel, CompleteLessonContent, ContentSubPointText, Term};
+use baml_client::models::{
+    ClassLevel, CompleteLessonContent, ContentFigure, ContentSubPoint, ContentSubPointText,
+    ContentTable, FigureKind, Language, Term, TranslatedLessonContent,
+};
 use derive_typst_intoval::{IntoDict, IntoValue};
 use std::fs;
 use typst::foundations::{Bytes, Dict, IntoValue};
+use typst::layout::PagedDocument;
 use typst_as_lib::TypstEngine;
 
-use crate::AgentError;
+use crate::utils::fonts::FontSet;
+use crate::utils::manual_strings::{manual_strings, ManualStrings};
+use crate::utils::markup::{plain_text, sanitize, sanitize_all};
+use crate::utils::pdf_cache::ManualOptions;
+use crate::utils::templates::ManualTemplate;
+use crate::utils::{is_hex_colour, is_valid_asset_tag, language_to_string, LessonAsset};
+use crate::{AgentError, SchoolBranding};
+
+pub mod docx;
+pub mod html;
 
 // File paths - these should be in your Golem agent's filesystem
-static TEMPLATE_PATH: &str = "/templates/template.typ";
-static FONT_PATH: &str = "/fonts/times-new-roman.ttf";
-static WATERMARK_PATH: &str = "/templates/images/watermark.png";
+// Illustrations shipped with the component, named <tag>.png, <tag>.jpg or <tag>.svg
+static IMAGE_LIBRARY_PATH: &str = "/templates/images/library";
+static IMAGE_EXTENSIONS: &[(&str, &str)] = &[
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("svg", "image/svg+xml"),
+];
+
+// Previews are thumbnails for the review UI, about 110 dpi
+const PREVIEW_PIXELS_PER_PT: f32 = 1.5;
+const MAX_PREVIEW_PAGES: usize = 10;
 
 pub fn pdf_engine(
     lessons: Vec<CompleteLessonContent>,
-    subject_name: &str,
-    class_year: &str,
-    mode: &str, // "pupil" or "teacher"
+    translation: Option<ManualTranslation>,
+    assets: Vec<LessonAsset>, // uploaded images for the tags the lessons and branding use
+    branding: SchoolBranding,
+    template: &ManualTemplate, // from the template registry, see templates::resolve_template
+    options: &ManualOptions,
+) -> Result<Vec<u8>, AgentError> {
+    let input = template_input(lessons, translation, assets, branding, options);
+    let pdf = compile_typst(&template.source, input)?;
+
+    // Optionally write to file
+    // fs::write(OUTPUT, &pdf).map_err(|e| AgentError {
+    //     message: format!("Could not write PDF: {}", e),
+    //     code: "FILE_WRITE_ERROR".to_string(),
+    // })?;
+
+    // Write to file (optional - for debugging/caching)
+    // if let Err(e) = fs::write(OUTPUT_PATH, &pdf) {
+    //     // Just log the error, don't fail if we can't write
+    //     eprintln!("Warning: Could not write PDF to {}: {}", OUTPUT_PATH, e);
+    // }
+
+    Ok(pdf)
+}
+
+// PNG images of the first pages of a single lesson, rendered through the same template
+// as the manual but without its front matter, appendices or question bank
+pub fn preview_engine(
+    lesson: CompleteLessonContent,
+    assets: Vec<LessonAsset>,
+    branding: SchoolBranding,
+    template: &ManualTemplate,
+    options: &ManualOptions,
+    page_count: usize,
+) -> Result<Vec<Vec<u8>>, AgentError> {
+    let options = ManualOptions {
+        question_bank: false,
+        ca_tests: false,
+        ..*options
+    };
+    let mut input = template_input(vec![lesson], None, assets, branding, &options);
+    input.preview = true;
+    input.show_index = false;
+    input.glossary_appendix.clear();
+
+    let doc = compile_document(&template.source, input)?;
+    doc.pages
+        .iter()
+        .take(page_count.min(MAX_PREVIEW_PAGES))
+        .map(|page| {
+            typst_render::render(page, PREVIEW_PIXELS_PER_PT)
+                .encode_png()
+                .map_err(|e| AgentError {
+                    message: format!("PNG encoding failed: {:?}", e),
+                    code: "PNG_ENCODING_ERROR".to_string(),
+                })
+        })
+        .collect()
+}
+
+fn template_input(
+    lessons: Vec<CompleteLessonContent>,
+    translation: Option<ManualTranslation>,
+    assets: Vec<LessonAsset>,
+    branding: SchoolBranding,
+    options: &ManualOptions,
+) -> TemplateInput {
+    let &ManualOptions {
+        subject_name,
+        class_year,
+        mode,
+        question_bank,
+        ca_tests,
+    } = options;
+    // convert incoming class string to corresponding string typst requires
+    let class_str = class_year_number(class_year);
+    // Convert lessons to typst input format
+    let question_counts = question_counts(class_year, mode, question_bank);
+    let mut lessons: Vec<Lesson> = lessons.into_iter().map(|l| l.into()).collect();
+    let (language, bilingual) = match translation {
+        Some(translation) => {
+            for (lesson, translated) in lessons.iter_mut().zip(translation.lessons) {
+                if let Some(translated) = translated {
+                    apply_translation(lesson, translated, translation.bilingual);
+                }
+            }
+            (
+                language_to_string(translation.language),
+                translation.bilingual,
+            )
+        }
+        None => ("ENGLISH".to_string(), false),
+    };
+    // Dropped after translating, translations pair glossary entries by position
+    for lesson in lessons.iter_mut() {
+        lesson
+            .glossary
+            .retain(|g| !plain_text(&g.term).trim().is_empty());
+    }
+    for section in lessons
+        .iter_mut()
+        .flat_map(|l| l.content_sections.iter_mut())
+    {
+        if let Some(image) = section.image.as_mut() {
+            if let Some((data, content_type)) = find_image(&image.tag, &assets) {
+                image.data = Some(Bytes::new(data));
+                image.content_type = Some(content_type);
+            }
+        }
+    }
+    // Bilingual manuals keep English headings, the translation follows each paragraph
+    let strings = if bilingual {
+        manual_strings("ENGLISH", &branding.name)
+    } else {
+        manual_strings(&language, &branding.name)
+    };
+    let glossary_appendix = glossary_appendix(&lessons);
+    // Index terms come from the final (possibly translated) text so they match the pages
+    let show_index = matches!(
+        class_year,
+        "PRIMARY_4" | "PRIMARY_5" | "JSS_1" | "JSS_2" | "JSS_3"
+    );
+    if show_index {
+        for lesson in lessons.iter_mut() {
+            lesson.index_terms = index_terms(lesson);
+        }
+    }
+    let bank = partition_questions(&mut lessons, question_counts);
+    TemplateInput {
+        subject_name: subject_name.to_string(),
+        class_year: class_str.to_string(),
+        mode: mode.to_string(),
+        language,
+        bilingual,
+        strings,
+        lessons,
+        question_counts,
+        question_bank: if question_bank { Some(bank) } else { None },
+        glossary_appendix,
+        show_index,
+        ca_tests,
+        preview: false,
+        branding: TypstBranding::new(branding, &assets),
+    }
+}
+
+// Compile a typst template from the component filesystem with the given inputs into PDF bytes
+pub(crate) fn compile_pdf<D: Into<Dict>>(
+    template_path: &str,
+    input: D,
 ) -> Result<Vec<u8>, AgentError> {
     // Read template file at runtime
-    let template_content = fs::read_to_string(TEMPLATE_PATH).map_err(|e| AgentError {
+    let template_content = fs::read_to_string(template_path).map_err(|e| AgentError {
         message: format!("Could not read template file: {}", e),
         code: "TEMPLATE_READ_ERROR".to_string(),
     })?;
+    compile_typst(&template_content, input)
+}
 
-    // Read font file at runtime
-    let font_bytes = fs::read(FONT_PATH).map_err(|e| AgentError {
-        message: format!("Could not read font file: {}", e),
-        code: "FONT_READ_ERROR".to_string(),
-    })?;
+// Compile typst source with the given inputs into PDF bytes
+pub(crate) fn compile_typst<D: Into<Dict>>(
+    template_content: &str,
+    input: D,
+) -> Result<Vec<u8>, AgentError> {
+    let doc = compile_document(template_content, input)?;
 
-    // Read watermark image at runtime
-    let watermark_bytes = fs::read(WATERMARK_PATH).map_err(|e| AgentError {
-        message: format!("Could not read watermark image: {}", e),
-        code: "IMAGE_READ_ERROR".to_string(),
-    })?; // Optional, so use .ok()
+    // Generate PDF
+    let options = Default::default();
+    typst_pdf::pdf(&doc, &options).map_err(|e| AgentError {
+        message: format!("PDF generation failed: {:?}", e),
+        code: "PDF_GENERATION_ERROR".to_string(),
+    })
+}
+
+fn compile_document<D: Into<Dict>>(
+    template_content: &str,
+    input: D,
+) -> Result<PagedDocument, AgentError> {
+    // Read font files at runtime
+    let fonts = FontSet::load().read()?;
 
     // Build the typst engine with the template and fonts
     let template = TypstEngine::builder()
-        .main_file(template_content.as_str())
-        .fonts([font_bytes.as_slice()])
+        .main_file(template_content)
+        .fonts(fonts.iter().map(|f| f.as_slice()))
         .build();
 
-    // convert incoming class string to corresponding string typst requires
-    let class_str = match class_year {
-        "PRIMARY_1" => "1",
-        "PRIMARY_2" => "2",
-        "PRIMARY_3" => "3",
-        "PRIMARY_4" => "4",
-        "PRIMARY_5" => "5",
-        _ => "",
-    };
-    // Convert lessons to typst input format
-    let input = TemplateInput {
-        subject_name: subject_name.to_string(),
-        class_year: class_str.to_string(),
-        mode: mode.to_string(),
-        lessons: lessons.into_iter().map(|l| l.into()).collect(),
-        watermark_image: Some(Bytes::new(watermark_bytes)),
-    };
-
     // Compile the template
-    let doc = template
+    template
         .compile_with_input(input)
         .output
         .map_err(|e| AgentError {
             message: format!("Typst compilation failed: {:?}", e),
             code: "TYPST_COMPILE_ERROR".to_string(),
-        })?;
-
-    // Generate PDF
-    let options = Default::default();
-    let pdf = typst_pdf::pdf(&doc, &options).map_err(|e| AgentError {
-        message: format!("PDF generation failed: {:?}", e),
-        code: "PDF_GENERATION_ERROR".to_string(),
-    })?;
-
-    // Optionally write to file
-    // fs::write(OUTPUT, &pdf).map_err(|e| AgentError {
-    //     message: format!("Could not write PDF: {}", e),
-    //     code: "FILE_WRITE_ERROR".to_string(),
-    // })?;
+        })
+}
 
-    // Write to file (optional - for debugging/caching)
-    // if let Err(e) = fs::write(OUTPUT_PATH, &pdf) {
-    //     // Just log the error, don't fail if we can't write
-    //     eprintln!("Warning: Could not write PDF to {}: {}", OUTPUT_PATH, e);
-    // }
+// Year digit the templates print for a class level, empty for JSS classes
+pub(crate) fn class_year_number(class_year: &str) -> &'static str {
+    match class_year {
+        "PRIMARY_1" => "1",
+        "PRIMARY_2" => "2",
+        "PRIMARY_3" => "3",
+        "PRIMARY_4" => "4",
+        "PRIMARY_5" => "5",
+        _ => "",
+    }
+}
 
-    Ok(pdf)
+// Translated variants of the lessons passed to pdf_engine, in the same order
+pub struct ManualTranslation {
+    pub language: Language,
+    pub bilingual: bool, // print the translation under the English instead of replacing it
+    pub lessons: Vec<Option<TranslatedLessonContent>>,
 }
 
 // Main input structure matching template's expectations
@@ -96,8 +256,65 @@ struct TemplateInput {
     subject_name: String,
     class_year: String,
     mode: String,
+    language: String,
+    bilingual: bool,
+    strings: ManualStrings,
     lessons: Vec<Lesson>,
-    watermark_image: Option<Bytes>,
+    question_counts: QuestionCounts,
+    question_bank: Option<Vec<QuestionBankTerm>>,
+    glossary_appendix: Vec<GlossaryEntry>,
+    show_index: bool,
+    ca_tests: bool,
+    preview: bool,
+    branding: TypstBranding,
+}
+
+// School branding with its logo and watermark images loaded for the template
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+pub(crate) struct TypstBranding {
+    name: String,
+    short_name: String,
+    motto: String,
+    address: String,
+    phones: Vec<String>,
+    email: String,
+    editions: Vec<String>,
+    logo: Option<Bytes>,
+    logo_content_type: Option<String>, // for exports that inline the logo, e.g. HTML
+    watermark: Option<Bytes>,
+    primary_colour: String,
+    accent_colour: String,
+}
+
+impl TypstBranding {
+    pub(crate) fn new(branding: SchoolBranding, assets: &[LessonAsset]) -> Self {
+        let image = |tag: &Option<String>| tag.as_deref().and_then(|t| load_image(t, assets));
+        let colour = |c: String| {
+            if is_hex_colour(&c) {
+                c
+            } else {
+                "#000000".to_string()
+            }
+        };
+        let logo = branding
+            .logo_tag
+            .as_deref()
+            .and_then(|t| find_image(t, assets));
+        TypstBranding {
+            logo_content_type: logo.as_ref().map(|(_, content_type)| content_type.clone()),
+            logo: logo.map(|(data, _)| Bytes::new(data)),
+            watermark: image(&branding.watermark_tag),
+            name: branding.name,
+            short_name: branding.short_name,
+            motto: branding.motto,
+            address: branding.address,
+            phones: branding.phones,
+            email: branding.email,
+            editions: branding.editions,
+            primary_colour: colour(branding.primary_colour),
+            accent_colour: colour(branding.accent_colour),
+        }
+    }
 }
 
 impl From<TemplateInput> for Dict {
@@ -115,17 +332,23 @@ struct Lesson {
     week: i32,
     term: String,
     topic_title: String,
+    translated_topic_title: Option<String>,
     duration_mins: i32,
     introduction: String,
+    translated_introduction: Option<String>,
     objectives: Vec<Objective>,
     materials: Vec<String>,
     prior_knowledge: Vec<String>,
     content_sections: Vec<TypstContentSection>,
     lesson_steps: Vec<TypstLessonStep>,
     key_points: Vec<String>,
+    translated_key_points: Option<Vec<String>>,
+    glossary: Vec<GlossaryEntry>,
+    index_terms: Vec<String>,
     mcq_questions: Vec<McqQuestion>,
     theoretical_questions: Vec<TypstTheoreticalQuestion>,
     conclusion: String,
+    translated_conclusion: Option<String>,
     teacher_tips: String,
     remediation: String,
     formative_assessment: String,
@@ -139,25 +362,293 @@ struct Lesson {
 #[derive(Debug, Clone, IntoValue, IntoDict)]
 struct Objective {
     objective: String,
+    translated_objective: Option<String>,
     taxonomy_level: String,
 }
 
 #[derive(Debug, Clone, IntoValue, IntoDict)]
 struct TypstContentSection {
+    section_number: i32,
     header: String,
+    translated_header: Option<String>,
     body: String,
+    translated_body: Option<String>,
+    tables: Vec<TypstTable>,
+    image: Option<SectionImage>,
+    figures: Vec<TypstFigure>,
     sub_points: Option<Vec<SubPoint>>,
 }
 
+// A diagram the template draws from data. Only the fields of its kind are used,
+// the others keep their defaults.
+#[derive(Debug, Clone, Default, IntoValue, IntoDict)]
+struct TypstFigure {
+    kind: String, // "SHAPE", "NUMBER_LINE", "BAR_CHART" or "CLOCK"
+    caption: String,
+    shape: String,
+    side_labels: Vec<String>,
+    start: i32,
+    end: i32,
+    step: i32,
+    marks: Vec<i32>,
+    categories: Vec<String>,
+    values: Vec<f64>,
+    y_label: Option<String>,
+    hour: i32,
+    minute: i32,
+}
+
+const FIGURE_SHAPES: &[&str] = &[
+    "triangle",
+    "square",
+    "rectangle",
+    "circle",
+    "pentagon",
+    "hexagon",
+];
+// More ticks than this and the number line labels run into each other
+const MAX_NUMBER_LINE_TICKS: i32 = 20;
+// Number lines in the curriculum stay well inside this, and keeping to it means the
+// tick arithmetic below can't overflow on whatever numbers the LLM returns
+const NUMBER_LINE_LIMIT: i32 = 1_000_000;
+const MAX_BARS: usize = 8;
+
+// Check the LLM's figure data and bring it into a range the template can draw,
+// dropping figures that can't be drawn rather than failing the manual
+fn convert_figure(figure: ContentFigure) -> Option<TypstFigure> {
+    let caption = sanitize(&figure.caption);
+    let converted = match figure.kind {
+        FigureKind::Shape => {
+            let shape = figure.shape.unwrap_or_default().trim().to_lowercase();
+            if !FIGURE_SHAPES.contains(&shape.as_str()) {
+                None
+            } else {
+                Some(TypstFigure {
+                    kind: "SHAPE".to_string(),
+                    caption: caption.clone(),
+                    shape,
+                    side_labels: sanitize_all(figure.side_labels.unwrap_or_default()),
+                    ..Default::default()
+                })
+            }
+        }
+        FigureKind::NumberLine => {
+            let start = figure.start.unwrap_or(0);
+            let end = figure.end.unwrap_or(10);
+            let in_range = |n: i32| (-NUMBER_LINE_LIMIT..=NUMBER_LINE_LIMIT).contains(&n);
+            if end <= start || !in_range(start) || !in_range(end) {
+                None
+            } else {
+                let min_step = (end - start + MAX_NUMBER_LINE_TICKS - 1) / MAX_NUMBER_LINE_TICKS;
+                Some(TypstFigure {
+                    kind: "NUMBER_LINE".to_string(),
+                    caption: caption.clone(),
+                    start,
+                    end,
+                    step: figure.step.unwrap_or(1).max(min_step).max(1),
+                    marks: figure
+                        .marks
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|m| (start..=end).contains(m))
+                        .collect(),
+                    ..Default::default()
+                })
+            }
+        }
+        FigureKind::BarChart => {
+            let mut categories = figure.categories.unwrap_or_default();
+            let mut values = figure.values.unwrap_or_default();
+            let bars = categories.len().min(values.len()).min(MAX_BARS);
+            categories.truncate(bars);
+            values.truncate(bars);
+            if bars == 0 {
+                None
+            } else {
+                Some(TypstFigure {
+                    kind: "BAR_CHART".to_string(),
+                    caption: caption.clone(),
+                    categories: sanitize_all(categories),
+                    values: values.into_iter().map(|v| v.max(0.0)).collect(),
+                    y_label: figure.y_label.as_deref().map(sanitize),
+                    ..Default::default()
+                })
+            }
+        }
+        FigureKind::Clock => Some(TypstFigure {
+            kind: "CLOCK".to_string(),
+            caption: caption.clone(),
+            hour: match figure.hour.unwrap_or(12).rem_euclid(12) {
+                0 => 12,
+                hour => hour,
+            },
+            minute: figure.minute.unwrap_or(0).clamp(0, 59),
+            ..Default::default()
+        }),
+    };
+    if converted.is_none() {
+        println!(
+            "⚠️  Skipping figure '{}' with data that can't be drawn",
+            caption
+        );
+    }
+    converted
+}
+
+// Illustration slot of a content section; the template draws a placeholder box
+// with the tag when no uploaded or shipped image matches
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct SectionImage {
+    tag: String,
+    caption: Option<String>,
+    data: Option<Bytes>,
+    content_type: Option<String>,
+}
+
+// Look the tag up in the school's uploaded assets first, then in the component filesystem,
+// so a school can replace a shipped illustration by uploading one under its tag
+pub(crate) fn load_image(tag: &str, assets: &[LessonAsset]) -> Option<Bytes> {
+    find_image(tag, assets).map(|(data, _)| Bytes::new(data))
+}
+
+// The image for a tag with its content type, looked up like load_image
+pub(crate) fn find_image(tag: &str, assets: &[LessonAsset]) -> Option<(Vec<u8>, String)> {
+    if !is_valid_asset_tag(tag) {
+        return None;
+    }
+    let image = assets
+        .iter()
+        .find(|a| a.tag == tag)
+        .and_then(|asset| Some((asset.bytes()?, asset.content_type.clone())))
+        .or_else(|| library_image(tag));
+    if image.is_none() {
+        println!("⚠️  No image for tag '{}', using a placeholder", tag);
+    }
+    image
+}
+
+// An illustration shipped with the component, with the content type of its extension
+pub(crate) fn library_image(tag: &str) -> Option<(Vec<u8>, String)> {
+    IMAGE_EXTENSIONS.iter().find_map(|(ext, content_type)| {
+        let data = fs::read(format!("{}/{}.{}", IMAGE_LIBRARY_PATH, tag, ext)).ok()?;
+        Some((data, content_type.to_string()))
+    })
+}
+
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct TypstTable {
+    caption: String,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+// Every row gets exactly one cell per header so typst lays the grid out as intended
+impl From<ContentTable> for TypstTable {
+    fn from(table: ContentTable) -> Self {
+        let columns = table.headers.len();
+        TypstTable {
+            caption: sanitize(&table.caption),
+            headers: sanitize_all(table.headers),
+            rows: table
+                .rows
+                .into_iter()
+                .filter(|row| !row.is_empty())
+                .map(|mut row| {
+                    row.resize(columns, String::new());
+                    sanitize_all(row)
+                })
+                .collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, IntoValue, IntoDict)]
 struct SubPoint {
     sub_number: String,
     text: SubPointText,
 }
 
+// A sub-point is either plain text or a nested section with its own header and
+// sub-points, which the template numbers one level deeper ((i) then (a))
 #[derive(Debug, Clone, IntoValue, IntoDict)]
 struct SubPointText {
+    section_number: Option<i32>,
+    header: Option<String>,
     body: String,
+    translated_body: Option<String>,
+    sub_points: Option<Vec<SubPoint>>,
+}
+
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct GlossaryEntry {
+    term: String,
+    definition: String,
+    translated_term: Option<String>,
+    translated_definition: Option<String>,
+    letter: String, // heading the entry is filed under in the glossary appendix
+}
+
+// Every lesson's new words in alphabetical order, keeping the first definition of repeated words.
+// Words are compared and filed by their plain text, so "**Atom**" goes under A next to "atom".
+fn glossary_appendix(lessons: &[Lesson]) -> Vec<GlossaryEntry> {
+    let mut entries: Vec<(String, GlossaryEntry)> = Vec::new();
+    for entry in lessons.iter().flat_map(|l| l.glossary.iter()) {
+        let key = plain_text(&entry.term).trim().to_lowercase();
+        if !entries.iter().any(|(k, _)| *k == key) {
+            let mut entry = entry.clone();
+            entry.letter = key
+                .chars()
+                .next()
+                .map(|c| c.to_uppercase().to_string())
+                .unwrap_or_default();
+            entries.push((key, entry));
+        }
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    entries.into_iter().map(|(_, entry)| entry).collect()
+}
+
+// Key points longer than this are sentences, not terms worth indexing
+const MAX_INDEX_TERM_WORDS: usize = 4;
+
+// Terms a lesson contributes to the back-of-book index: section headers,
+// glossary words and key points short enough to be a term
+fn index_terms(lesson: &Lesson) -> Vec<String> {
+    let candidates = lesson
+        .content_sections
+        .iter()
+        .map(|cs| cs.header.as_str())
+        .chain(lesson.glossary.iter().map(|g| g.term.as_str()))
+        .chain(
+            lesson
+                .key_points
+                .iter()
+                .map(|kp| kp.as_str())
+                .filter(|kp| kp.split_whitespace().count() <= MAX_INDEX_TERM_WORDS),
+        );
+
+    let mut terms: Vec<String> = Vec::new();
+    for candidate in candidates {
+        let term = normalize_index_term(candidate);
+        if !term.is_empty() && !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+    terms
+}
+
+// "THE HEAD:" and "the head" both index as "The head". Formatting is dropped
+// and the term escaped again so it renders like any other lesson text.
+fn normalize_index_term(text: &str) -> String {
+    let lower = plain_text(text)
+        .trim()
+        .trim_end_matches(|c: char| c.is_ascii_punctuation())
+        .to_lowercase();
+    let mut chars = lower.chars();
+    match chars.next() {
+        Some(first) => sanitize(&first.to_uppercase().chain(chars).collect::<String>()),
+        None => String::new(),
+    }
 }
 
 #[derive(Debug, Clone, IntoValue, IntoDict)]
@@ -174,9 +665,11 @@ struct TypstLessonStep {
 #[derive(Debug, Clone, IntoValue, IntoDict)]
 struct McqQuestion {
     question: String,
+    translated_question: Option<String>,
     option_a: String,
     option_b: String,
     option_c: String,
+    translated_options: Option<Vec<String>>,
     correct_answer: String,
     explanation: String,
 }
@@ -184,13 +677,176 @@ struct McqQuestion {
 #[derive(Debug, Clone, IntoValue, IntoDict)]
 struct TypstTheoreticalQuestion {
     question: String,
+    translated_question: Option<String>,
     parts: Vec<String>,
+    translated_parts: Option<Vec<String>>,
     model_answer: String,
     marking_scheme: String,
 }
 
+// Number of MCQs and theory questions shown inside each lesson, the rest go to the bank
+#[derive(Debug, Clone, Copy, IntoValue, IntoDict)]
+struct QuestionCounts {
+    mcq: i32,
+    theory: i32,
+}
+
+// Lower primary pupils get short exercises, JSS students work through every question.
+// Without a bank, teachers get every question in place so no answers are lost.
+fn question_counts(class_year: &str, mode: &str, question_bank: bool) -> QuestionCounts {
+    if mode == "teacher" && !question_bank {
+        return QuestionCounts { mcq: 9, theory: 9 };
+    }
+    match class_year {
+        "PRIMARY_1" | "PRIMARY_2" | "PRIMARY_3" => QuestionCounts { mcq: 3, theory: 3 },
+        "PRIMARY_4" | "PRIMARY_5" => QuestionCounts { mcq: 5, theory: 5 },
+        _ => QuestionCounts { mcq: 9, theory: 9 },
+    }
+}
+
+// Questions left over after each lesson's share, grouped by term for the exam bank
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct QuestionBankTerm {
+    term: String,
+    mcq_questions: Vec<McqQuestion>,
+    theoretical_questions: Vec<TypstTheoreticalQuestion>,
+}
+
+// Keep the first questions of every lesson in place and move the rest into the bank.
+// Lessons arrive ordered by term, so consecutive lessons of a term share one entry.
+fn partition_questions(lessons: &mut [Lesson], counts: QuestionCounts) -> Vec<QuestionBankTerm> {
+    let mcq_count = counts.mcq.max(0) as usize;
+    let theory_count = counts.theory.max(0) as usize;
+    let mut bank: Vec<QuestionBankTerm> = Vec::new();
+    for lesson in lessons.iter_mut() {
+        let mcq_questions = if lesson.mcq_questions.len() > mcq_count {
+            lesson.mcq_questions.split_off(mcq_count)
+        } else {
+            Vec::new()
+        };
+        let theoretical_questions = if lesson.theoretical_questions.len() > theory_count {
+            lesson.theoretical_questions.split_off(theory_count)
+        } else {
+            Vec::new()
+        };
+
+        match bank.last_mut() {
+            Some(entry) if entry.term == lesson.term => {
+                entry.mcq_questions.extend(mcq_questions);
+                entry.theoretical_questions.extend(theoretical_questions);
+            }
+            _ => bank.push(QuestionBankTerm {
+                term: lesson.term.clone(),
+                mcq_questions,
+                theoretical_questions,
+            }),
+        }
+    }
+    bank
+}
+
+// Swap in the translated pupil-facing text, or keep the English and attach the
+// translation next to it for bilingual manuals. Lists are matched by position.
+fn apply_translation(lesson: &mut Lesson, translated: TranslatedLessonContent, bilingual: bool) {
+    let translate = |original: &mut String, slot: &mut Option<String>, text: String| {
+        let text = sanitize(&text);
+        if bilingual {
+            *slot = Some(text);
+        } else {
+            *original = text;
+        }
+    };
+
+    translate(
+        &mut lesson.topic_title,
+        &mut lesson.translated_topic_title,
+        translated.topic_title,
+    );
+    translate(
+        &mut lesson.introduction,
+        &mut lesson.translated_introduction,
+        translated.introduction,
+    );
+    translate(
+        &mut lesson.conclusion,
+        &mut lesson.translated_conclusion,
+        translated.conclusion,
+    );
+    for (objective, text) in lesson.objectives.iter_mut().zip(translated.objectives) {
+        translate(
+            &mut objective.objective,
+            &mut objective.translated_objective,
+            text,
+        );
+    }
+    for (section, ts) in lesson
+        .content_sections
+        .iter_mut()
+        .zip(translated.content_sections)
+    {
+        translate(
+            &mut section.header,
+            &mut section.translated_header,
+            ts.header,
+        );
+        translate(&mut section.body, &mut section.translated_body, ts.body);
+        if let Some(sub_points) = section.sub_points.as_mut() {
+            for (sp, text) in sub_points.iter_mut().zip(ts.sub_points) {
+                translate(&mut sp.text.body, &mut sp.text.translated_body, text);
+            }
+        }
+    }
+    if bilingual {
+        lesson.translated_key_points = Some(sanitize_all(translated.key_points));
+    } else {
+        for (key_point, text) in lesson.key_points.iter_mut().zip(translated.key_points) {
+            *key_point = sanitize(&text);
+        }
+    }
+    for (entry, tg) in lesson
+        .glossary
+        .iter_mut()
+        .zip(translated.glossary.unwrap_or_default())
+    {
+        translate(&mut entry.term, &mut entry.translated_term, tg.term);
+        translate(
+            &mut entry.definition,
+            &mut entry.translated_definition,
+            tg.definition,
+        );
+    }
+    for (q, tq) in lesson
+        .mcq_questions
+        .iter_mut()
+        .zip(translated.mcq_questions)
+    {
+        translate(&mut q.question, &mut q.translated_question, tq.question);
+        if bilingual {
+            q.translated_options = Some(sanitize_all(vec![tq.option_a, tq.option_b, tq.option_c]));
+        } else {
+            q.option_a = sanitize(&tq.option_a);
+            q.option_b = sanitize(&tq.option_b);
+            q.option_c = sanitize(&tq.option_c);
+        }
+    }
+    for (q, tq) in lesson
+        .theoretical_questions
+        .iter_mut()
+        .zip(translated.theoretical_questions)
+    {
+        translate(&mut q.question, &mut q.translated_question, tq.question);
+        if bilingual {
+            q.translated_parts = Some(sanitize_all(tq.parts));
+        } else {
+            for (part, text) in q.parts.iter_mut().zip(tq.parts) {
+                *part = sanitize(&text);
+            }
+        }
+    }
+}
+
 // Helper function to convert ClassLevel enum to string
-fn class_level_to_string(level: ClassLevel) -> String {
+pub(crate) fn class_level_to_string(level: ClassLevel) -> String {
     match level {
         ClassLevel::Primary1 => "PRIMARY_1".to_string(),
         ClassLevel::Primary2 => "PRIMARY_2".to_string(),
@@ -204,7 +860,7 @@ fn class_level_to_string(level: ClassLevel) -> String {
 }
 
 // Helper function to convert Term enum to string
-fn term_to_string(term: Term) -> String {
+pub(crate) fn term_to_string(term: Term) -> String {
     match term {
         Term::First => "FIRST".to_string(),
         Term::Second => "SECOND".to_string(),
@@ -212,15 +868,34 @@ fn term_to_string(term: Term) -> String {
     }
 }
 
-// Helper function to extract text from ContentSubPointText enum
-fn extract_text_from_subpoint(text: ContentSubPointText) -> String {
-    match text {
-        ContentSubPointText::String(s) => s,
-        ContentSubPointText::ContentSection(cs) => cs.body,
+// Convert a sub-point, keeping nested sections with their header and sub-points
+fn convert_sub_point(sub_point: ContentSubPoint) -> SubPoint {
+    let text = match sub_point.text {
+        ContentSubPointText::String(s) => SubPointText {
+            section_number: None,
+            header: None,
+            body: sanitize(&s),
+            translated_body: None,
+            sub_points: None,
+        },
+        ContentSubPointText::ContentSection(cs) => SubPointText {
+            section_number: Some(cs.section_number),
+            header: Some(sanitize(&cs.header)),
+            body: sanitize(&cs.body),
+            translated_body: None,
+            sub_points: cs
+                .sub_points
+                .map(|sps| sps.into_iter().map(convert_sub_point).collect()),
+        },
+    };
+    SubPoint {
+        sub_number: sanitize(&sub_point.sub_number),
+        text,
     }
 }
 
-// Implement conversion from CompleteLessonContent to Lesson
+// Implement conversion from CompleteLessonContent to Lesson.
+// All LLM text is escaped into typst markup here, see utils::markup.
 impl From<CompleteLessonContent> for Lesson {
     fn from(content: CompleteLessonContent) -> Self {
         Lesson {
@@ -229,35 +904,53 @@ impl From<CompleteLessonContent> for Lesson {
             subject: content.subject,
             week: content.week,
             term: term_to_string(content.term),
-            topic_title: content.topic_title,
+            topic_title: sanitize(&content.topic_title),
+            translated_topic_title: None,
             duration_mins: content.duration_mins,
-            introduction: content.introduction,
+            introduction: sanitize(&content.introduction),
+            translated_introduction: None,
             objectives: content
                 .objectives
                 .into_iter()
                 .map(|o| Objective {
-                    objective: o.objective,
-                    taxonomy_level: o.taxonomy_level,
+                    objective: sanitize(&o.objective),
+                    translated_objective: None,
+                    taxonomy_level: sanitize(&o.taxonomy_level),
                 })
                 .collect(),
-            materials: content.materials,
-            prior_knowledge: content.prior_knowledge,
+            materials: sanitize_all(content.materials),
+            prior_knowledge: sanitize_all(content.prior_knowledge),
             content_sections: content
                 .content_sections
                 .into_iter()
                 .map(|cs| TypstContentSection {
-                    header: cs.header,
-                    body: cs.body,
-                    sub_points: cs.sub_points.map(|sps| {
-                        sps.into_iter()
-                            .map(|sp| SubPoint {
-                                sub_number: sp.sub_number,
-                                text: SubPointText {
-                                    body: extract_text_from_subpoint(sp.text),
-                                },
-                            })
-                            .collect()
+                    section_number: cs.section_number,
+                    header: sanitize(&cs.header),
+                    translated_header: None,
+                    body: sanitize(&cs.body),
+                    translated_body: None,
+                    tables: cs
+                        .tables
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|t| !t.headers.is_empty())
+                        .map(TypstTable::from)
+                        .collect(),
+                    image: cs.image_tag.map(|tag| SectionImage {
+                        tag: tag.trim().to_lowercase(),
+                        caption: cs.image_caption.as_deref().map(sanitize),
+                        data: None,
+                        content_type: None,
                     }),
+                    figures: cs
+                        .figures
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter_map(convert_figure)
+                        .collect(),
+                    sub_points: cs
+                        .sub_points
+                        .map(|sps| sps.into_iter().map(convert_sub_point).collect()),
                 })
                 .collect(),
             lesson_steps: content
@@ -265,46 +958,107 @@ impl From<CompleteLessonContent> for Lesson {
                 .into_iter()
                 .map(|ls| TypstLessonStep {
                     step_number: ls.step_number,
-                    phase: ls.phase,
+                    phase: sanitize(&ls.phase),
                     duration_mins: ls.duration_mins,
-                    teacher_actions: ls.teacher_actions,
-                    pupil_activities: ls.pupil_activities,
-                    teaching_strategy: ls.teaching_strategy,
-                    assessment: ls.assessment,
+                    teacher_actions: sanitize(&ls.teacher_actions),
+                    pupil_activities: sanitize(&ls.pupil_activities),
+                    teaching_strategy: sanitize(&ls.teaching_strategy),
+                    assessment: ls.assessment.as_deref().map(sanitize),
+                })
+                .collect(),
+            key_points: sanitize_all(content.key_points),
+            translated_key_points: None,
+            glossary: content
+                .glossary
+                .unwrap_or_default()
+                .into_iter()
+                .map(|g| GlossaryEntry {
+                    term: sanitize(&g.term),
+                    definition: sanitize(&g.definition),
+                    translated_term: None,
+                    translated_definition: None,
+                    letter: String::new(),
                 })
                 .collect(),
-            key_points: content.key_points,
+            index_terms: Vec::new(),
             mcq_questions: content
                 .mcq_questions
                 .into_iter()
                 .map(|mcq| McqQuestion {
-                    question: mcq.question,
-                    option_a: mcq.option_a,
-                    option_b: mcq.option_b,
-                    option_c: mcq.option_c,
+                    question: sanitize(&mcq.question),
+                    translated_question: None,
+                    option_a: sanitize(&mcq.option_a),
+                    option_b: sanitize(&mcq.option_b),
+                    option_c: sanitize(&mcq.option_c),
+                    translated_options: None,
                     correct_answer: mcq.correct_answer,
-                    explanation: mcq.explanation,
+                    explanation: sanitize(&mcq.explanation),
                 })
                 .collect(),
             theoretical_questions: content
                 .theoretical_questions
                 .into_iter()
                 .map(|tq| TypstTheoreticalQuestion {
-                    question: tq.question,
-                    parts: tq.parts,
-                    model_answer: tq.model_answer,
-                    marking_scheme: tq.marking_scheme,
+                    question: sanitize(&tq.question),
+                    translated_question: None,
+                    parts: sanitize_all(tq.parts),
+                    translated_parts: None,
+                    model_answer: sanitize(&tq.model_answer),
+                    marking_scheme: sanitize(&tq.marking_scheme),
                 })
                 .collect(),
-            conclusion: content.conclusion,
-            teacher_tips: content.teacher_tips,
-            remediation: content.remediation,
-            formative_assessment: content.formative_assessment,
-            summative_assessment: content.summative_assessment,
-            extension_activities: content.extension_activities,
-            primary_sources: content.primary_sources,
-            success_criteria: content.success_criteria,
-            textbook_references: content.textbook_references,
+            conclusion: sanitize(&content.conclusion),
+            translated_conclusion: None,
+            teacher_tips: sanitize(&content.teacher_tips),
+            remediation: sanitize(&content.remediation),
+            formative_assessment: sanitize(&content.formative_assessment),
+            summative_assessment: sanitize(&content.summative_assessment),
+            extension_activities: sanitize_all(content.extension_activities),
+            primary_sources: sanitize_all(content.primary_sources),
+            success_criteria: sanitize_all(content.success_criteria),
+            textbook_references: sanitize_all(content.textbook_references),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uploaded_assets_are_found_with_their_content_type() {
+        let assets = vec![LessonAsset {
+            tag: "human-body".to_string(),
+            content_type: "image/webp".to_string(),
+            data: "UklGRg==".to_string(),
+        }];
+        assert_eq!(
+            find_image("human-body", &assets),
+            Some((b"RIFF".to_vec(), "image/webp".to_string()))
+        );
+        assert_eq!(find_image("../human-body", &assets), None);
+        assert_eq!(find_image("missing-tag", &assets), None);
+    }
+
+    fn number_line(start: i32, end: i32, step: i32) -> Option<TypstFigure> {
+        convert_figure(
+            serde_json::from_value(serde_json::json!({
+                "kind": "NUMBER_LINE",
+                "caption": "Counting",
+                "start": start,
+                "end": end,
+                "step": step,
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn number_lines_keep_to_a_drawable_range() {
+        let figure = number_line(0, 100, 1).unwrap();
+        assert_eq!((figure.start, figure.end, figure.step), (0, 100, 5));
+        assert!(number_line(10, 0, 1).is_none());
+        assert!(number_line(i32::MIN, i32::MAX, 1).is_none());
+        assert!(number_line(0, i32::MAX, 1).is_none());
+    }
+}
@@ -0,0 +1,573 @@
+// Word version of a manual for teachers who edit the lessons before printing. It is built
+// from the same TemplateInput as the PDF, so lessons, translations and question counts
+// match the printed manual, but headings, tables and sub-point numbering come out as
+// ordinary Word paragraphs that are easy to change. Lesson text is typst markup at this
+// point and markup_runs turns it into formatted runs.
+
+use baml_client::models::CompleteLessonContent;
+use docx_rs::{
+    AlignmentType, BreakType, Docx, Paragraph, Run, RunFonts, SpecialIndentType, Style, StyleType,
+    Table, TableCell, TableRow,
+};
+use std::io::Cursor;
+
+use super::html::term_name;
+use super::{
+    template_input, Lesson, ManualTranslation, SubPoint, TemplateInput, TypstContentSection,
+};
+use crate::utils::markup::{markup_runs, plain_text};
+use crate::utils::pdf_cache::ManualOptions;
+use crate::{AgentError, SchoolBranding};
+
+// Word measures indents in twentieths of a point, half an inch per sub-point level
+const INDENT_STEP: i32 = 720;
+const LABEL_WIDTH: i32 = 360;
+// Translations are printed in grey under the English, as in the bilingual manual
+const TRANSLATION_COLOUR: &str = "505050";
+
+pub fn docx_engine(
+    lessons: Vec<CompleteLessonContent>,
+    subject_name: &str,
+    class_year: &str,
+    mode: &str, // "pupil" or "teacher"
+    translation: Option<ManualTranslation>,
+) -> Result<Vec<u8>, AgentError> {
+    if mode != "pupil" && mode != "teacher" {
+        return Err(AgentError {
+            message: format!(
+                "DOCX export supports pupil and teacher modes, got '{}'",
+                mode
+            ),
+            code: "INVALID_MODE".to_string(),
+        });
+    }
+    if lessons.is_empty() {
+        return Err(AgentError {
+            message: format!("No lessons found for {} {}", subject_name, class_year),
+            code: "NO_LESSONS".to_string(),
+        });
+    }
+    let options = ManualOptions {
+        subject_name,
+        class_year,
+        mode,
+        question_bank: false,
+        ca_tests: false,
+    };
+    // The Word file has no letterhead, so there are no branding images to load
+    let branding = SchoolBranding {
+        logo_tag: None,
+        watermark_tag: None,
+        ..Default::default()
+    };
+    let input = template_input(lessons, translation, Vec::new(), branding, &options);
+
+    let mut cursor = Cursor::new(Vec::new());
+    render_manual(&input)
+        .build()
+        .pack(&mut cursor)
+        .map_err(|e| AgentError {
+            message: format!("Failed to write DOCX: {:?}", e),
+            code: "DOCX_ERROR".to_string(),
+        })?;
+    Ok(cursor.into_inner())
+}
+
+fn render_manual(input: &TemplateInput) -> Docx {
+    let strings = &input.strings;
+    let title = format!(
+        "{} {} ({} {})",
+        input.subject_name, strings.manual_label, strings.year_label, input.class_year
+    );
+    let mut docx = Docx::new()
+        .default_fonts(
+            RunFonts::new()
+                .ascii("Times New Roman")
+                .hi_ansi("Times New Roman"),
+        )
+        .default_size(24)
+        .add_style(heading_style("Title", "Title", 40))
+        .add_style(heading_style("Heading1", "Heading 1", 32))
+        .add_style(heading_style("Heading2", "Heading 2", 26))
+        .add_style(heading_style("Heading3", "Heading 3", 24))
+        .add_paragraph(
+            Paragraph::new()
+                .style("Title")
+                .align(AlignmentType::Center)
+                .add_run(Run::new().add_text(title.to_uppercase())),
+        );
+
+    let mut last_term: Option<&str> = None;
+    for lesson in &input.lessons {
+        if last_term != Some(lesson.term.as_str()) {
+            docx = docx.add_paragraph(
+                Paragraph::new()
+                    .style("Title")
+                    .align(AlignmentType::Center)
+                    .add_run(Run::new().add_text(term_name(strings, &lesson.term).to_uppercase())),
+            );
+            last_term = Some(lesson.term.as_str());
+        }
+        docx = add_lesson(docx, input, lesson);
+    }
+    docx
+}
+
+fn add_lesson(mut docx: Docx, input: &TemplateInput, lesson: &Lesson) -> Docx {
+    let strings = &input.strings;
+    let teacher = input.mode == "teacher";
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .style("Heading1")
+            .add_run(Run::new().add_text(format!(
+                "{} {}: {}",
+                strings.topic_label,
+                lesson.week,
+                plain_text(&lesson.topic_title).to_uppercase()
+            ))),
+    );
+    docx = add_translation(docx, &lesson.translated_topic_title);
+
+    docx = docx.add_paragraph(heading(&strings.lesson_objectives));
+    for (i, objective) in lesson.objectives.iter().enumerate() {
+        docx = docx.add_paragraph(labelled(
+            &format!("{}.", i + 1),
+            &format!("{} ({})", objective.objective, objective.taxonomy_level),
+            0,
+        ));
+        docx = add_translation(docx, &objective.translated_objective);
+    }
+
+    if teacher {
+        docx = docx.add_paragraph(heading(&strings.previous_knowledge));
+        docx = add_bullets(docx, &lesson.prior_knowledge);
+        docx = docx.add_paragraph(heading(&strings.instructional_materials));
+        docx = add_bullets(docx, &lesson.materials);
+        docx = docx
+            .add_paragraph(heading(&strings.teacher_preparation))
+            .add_paragraph(
+                Paragraph::new()
+                    .add_run(
+                        Run::new()
+                            .add_text(format!("{} ", strings.duration_label))
+                            .bold(),
+                    )
+                    .add_run(
+                        Run::new()
+                            .add_text(format!("{} {}", lesson.duration_mins, strings.minutes)),
+                    ),
+            );
+    }
+
+    docx = docx
+        .add_paragraph(heading(&strings.lesson_content))
+        .add_paragraph(text(&lesson.introduction));
+    docx = add_translation(docx, &lesson.translated_introduction);
+    for section in &lesson.content_sections {
+        docx = add_section(docx, section);
+    }
+    docx = docx
+        .add_paragraph(
+            Paragraph::new().add_run(Run::new().add_text(strings.conclusion.clone()).bold()),
+        )
+        .add_paragraph(text(&lesson.conclusion));
+    docx = add_translation(docx, &lesson.translated_conclusion);
+
+    docx = docx.add_paragraph(heading(&strings.key_points));
+    for (i, key_point) in lesson.key_points.iter().enumerate() {
+        docx = docx.add_paragraph(labelled(&format!("{}.", i + 1), key_point, 0));
+        let translated = lesson
+            .translated_key_points
+            .as_ref()
+            .and_then(|t| t.get(i).cloned());
+        docx = add_translation(docx, &translated);
+    }
+
+    if !teacher && !lesson.glossary.is_empty() {
+        docx = docx.add_paragraph(heading(&strings.new_words));
+        for entry in &lesson.glossary {
+            let term = match &entry.translated_term {
+                Some(translated) => format!("{} ({}): ", entry.term, translated),
+                None => format!("{}: ", entry.term),
+            };
+            let paragraph = add_text(Paragraph::new(), &term, |run| run.bold());
+            docx = docx.add_paragraph(add_text(paragraph, &entry.definition, |run| run));
+            docx = add_translation(docx, &entry.translated_definition);
+        }
+    }
+
+    if teacher && !lesson.lesson_steps.is_empty() {
+        let mut rows = vec![TableRow::new(
+            [
+                &strings.step_label,
+                &strings.phase_label,
+                &strings.duration_label,
+                &strings.teacher_actions,
+                &strings.pupil_activities,
+            ]
+            .into_iter()
+            .map(|h| {
+                TableCell::new()
+                    .add_paragraph(Paragraph::new().add_run(Run::new().add_text(h.clone()).bold()))
+            })
+            .collect(),
+        )];
+        for step in &lesson.lesson_steps {
+            rows.push(TableRow::new(vec![
+                TableCell::new().add_paragraph(plain(&step.step_number.to_string())),
+                TableCell::new().add_paragraph(text(&step.phase)),
+                TableCell::new().add_paragraph(plain(&format!(
+                    "{} {}",
+                    step.duration_mins, strings.minutes
+                ))),
+                TableCell::new().add_paragraph(text(&step.teacher_actions)),
+                TableCell::new().add_paragraph(text(&step.pupil_activities)),
+            ]));
+        }
+        docx = docx
+            .add_paragraph(heading(&strings.lesson_steps))
+            .add_table(Table::new(rows));
+    }
+
+    if teacher {
+        docx = docx
+            .add_paragraph(heading(&strings.formative_assessment))
+            .add_paragraph(text(&lesson.formative_assessment))
+            .add_paragraph(heading(&strings.summative_assessment))
+            .add_paragraph(text(&lesson.summative_assessment))
+            .add_paragraph(heading(&strings.success_criteria));
+        docx = add_bullets(docx, &lesson.success_criteria);
+    }
+
+    // Only the questions the printed manual shows in the lesson
+    let mcq_count = (input.question_counts.mcq.max(0) as usize).min(lesson.mcq_questions.len());
+    if mcq_count > 0 {
+        docx = docx.add_paragraph(heading(&strings.revision_questions));
+        for (i, q) in lesson.mcq_questions[..mcq_count].iter().enumerate() {
+            docx = docx.add_paragraph(labelled(&format!("{}.", i + 1), &q.question, 0));
+            docx = add_translation(docx, &q.translated_question);
+            let translated_options = q.translated_options.as_deref().unwrap_or_default();
+            for (j, option) in [&q.option_a, &q.option_b, &q.option_c]
+                .into_iter()
+                .enumerate()
+            {
+                docx = docx.add_paragraph(labelled(&format!("({})", letter(j)), option, 1));
+                docx = add_translation(docx, &translated_options.get(j).cloned());
+            }
+            if teacher {
+                docx = docx.add_paragraph(answer(
+                    &format!("{} {}", strings.answer_label, q.correct_answer),
+                    &format!(
+                        "{} {}",
+                        strings.explanation_label,
+                        plain_text(&q.explanation)
+                    ),
+                ));
+            }
+        }
+    }
+
+    let theory_count =
+        (input.question_counts.theory.max(0) as usize).min(lesson.theoretical_questions.len());
+    if theory_count > 0 {
+        docx = docx.add_paragraph(heading(&strings.theoretical_questions));
+        for (i, q) in lesson.theoretical_questions[..theory_count]
+            .iter()
+            .enumerate()
+        {
+            docx = docx.add_paragraph(labelled(&format!("{}.", i + 1), &q.question, 0));
+            docx = add_translation(docx, &q.translated_question);
+            for (j, part) in q.parts.iter().enumerate() {
+                docx = docx.add_paragraph(labelled(&format!("({})", letter(j)), part, 1));
+            }
+            for part in q.translated_parts.iter().flatten() {
+                docx = add_translation(docx, &Some(part.clone()));
+            }
+            if teacher {
+                docx = docx.add_paragraph(answer(
+                    &format!("{} {}", strings.answer_label, plain_text(&q.model_answer)),
+                    &plain_text(&q.marking_scheme),
+                ));
+            }
+        }
+    }
+
+    if teacher {
+        docx = docx.add_paragraph(heading(&strings.extension_activities));
+        docx = add_bullets(docx, &lesson.extension_activities);
+        docx = docx
+            .add_paragraph(heading(&strings.remediation))
+            .add_paragraph(text(&lesson.remediation))
+            .add_paragraph(heading(&strings.references));
+        docx = add_bullets(docx, &lesson.textbook_references);
+        docx = add_bullets(docx, &lesson.primary_sources);
+    }
+
+    // Each lesson starts on a new page, as in the printed manual
+    docx.add_paragraph(Paragraph::new().add_run(Run::new().add_break(BreakType::Page)))
+}
+
+fn add_section(mut docx: Docx, section: &TypstContentSection) -> Docx {
+    docx = docx.add_paragraph(
+        Paragraph::new()
+            .style("Heading3")
+            .add_run(Run::new().add_text(format!(
+                "{}. {}",
+                section.section_number,
+                plain_text(&section.header).to_uppercase()
+            ))),
+    );
+    docx = add_translation(docx, &section.translated_header);
+    docx = docx.add_paragraph(text(&section.body));
+    docx = add_translation(docx, &section.translated_body);
+
+    // Pictures and figures aren't exported, a note keeps their place for the teacher
+    if let Some(image) = &section.image {
+        let caption = image
+            .caption
+            .as_deref()
+            .map(plain_text)
+            .unwrap_or_else(|| image.tag.clone());
+        docx = docx.add_paragraph(note(&format!("[Picture: {}]", caption)));
+    }
+    for figure in &section.figures {
+        docx = docx.add_paragraph(note(&format!("[Figure: {}]", plain_text(&figure.caption))));
+    }
+
+    for table in &section.tables {
+        let mut rows = vec![TableRow::new(
+            table
+                .headers
+                .iter()
+                .map(|h| {
+                    TableCell::new().add_paragraph(add_text(Paragraph::new(), h, |run| run.bold()))
+                })
+                .collect(),
+        )];
+        // Rows already have one cell per header, or Word would report the file as damaged
+        for row in &table.rows {
+            rows.push(TableRow::new(
+                row.iter()
+                    .map(|cell| TableCell::new().add_paragraph(text(cell)))
+                    .collect(),
+            ));
+        }
+        docx = docx
+            .add_paragraph(note(&plain_text(&table.caption)))
+            .add_table(Table::new(rows));
+    }
+
+    if let Some(sub_points) = &section.sub_points {
+        docx = add_sub_points(docx, sub_points, 0);
+    }
+    docx
+}
+
+// Sub-points keep the numbering the lesson was written with, e.g. (i), (ii) or (a), (b),
+// indented one step per level of nesting
+fn add_sub_points(mut docx: Docx, sub_points: &[SubPoint], level: i32) -> Docx {
+    for sub_point in sub_points {
+        let point = &sub_point.text;
+        let label = plain_text(&sub_point.sub_number);
+        match &point.header {
+            None => {
+                docx = docx.add_paragraph(labelled(&label, &point.body, level));
+            }
+            Some(header) => {
+                docx = docx
+                    .add_paragraph(add_text(labelled_paragraph(&label, level), header, |run| {
+                        run.bold()
+                    }))
+                    .add_paragraph(text(&point.body).indent(
+                        Some(INDENT_STEP * level + LABEL_WIDTH),
+                        None,
+                        None,
+                        None,
+                    ));
+            }
+        }
+        docx = add_translation(docx, &point.translated_body);
+        if let Some(nested) = &point.sub_points {
+            docx = add_sub_points(docx, nested, level + 1);
+        }
+    }
+    docx
+}
+
+fn heading_style(id: &str, name: &str, size: usize) -> Style {
+    Style::new(id, StyleType::Paragraph)
+        .name(name)
+        .size(size)
+        .bold()
+}
+
+fn heading(label: &str) -> Paragraph {
+    Paragraph::new()
+        .style("Heading2")
+        .add_run(Run::new().add_text(label.to_uppercase()))
+}
+
+fn plain(text: &str) -> Paragraph {
+    Paragraph::new().add_run(Run::new().add_text(text))
+}
+
+fn text(markup: &str) -> Paragraph {
+    add_text(Paragraph::new(), markup, |run| run)
+}
+
+// Lesson markup with its bold, italic and math spans as Word formatting, and its line
+// breaks as Word line breaks. `style` is applied to every run on top of that.
+fn add_text(mut paragraph: Paragraph, markup: &str, style: impl Fn(Run) -> Run) -> Paragraph {
+    for piece in markup_runs(markup) {
+        for (i, line) in piece.text.split('\n').enumerate() {
+            let mut run = Run::new();
+            if i > 0 {
+                run = run.add_break(BreakType::TextWrapping);
+            }
+            run = run.add_text(line);
+            if piece.strong {
+                run = run.bold();
+            }
+            if piece.emph || piece.math {
+                run = run.italic();
+            }
+            paragraph = paragraph.add_run(style(run));
+        }
+    }
+    paragraph
+}
+
+// Translation printed under the English text in bilingual manuals
+fn add_translation(docx: Docx, translated: &Option<String>) -> Docx {
+    match translated {
+        Some(translated) => docx.add_paragraph(add_text(Paragraph::new(), translated, |run| {
+            run.italic().color(TRANSLATION_COLOUR)
+        })),
+        None => docx,
+    }
+}
+
+// Paragraph starting with a list label, with the text wrapping under itself
+fn labelled(label: &str, markup: &str, level: i32) -> Paragraph {
+    add_text(labelled_paragraph(label, level), markup, |run| run)
+}
+
+fn labelled_paragraph(label: &str, level: i32) -> Paragraph {
+    Paragraph::new()
+        .indent(
+            Some(INDENT_STEP * level + LABEL_WIDTH),
+            Some(SpecialIndentType::Hanging(LABEL_WIDTH)),
+            None,
+            None,
+        )
+        .add_run(Run::new().add_text(label).add_tab())
+}
+
+fn add_bullets(mut docx: Docx, items: &[String]) -> Docx {
+    for item in items {
+        docx = docx.add_paragraph(labelled("•", item, 0));
+    }
+    docx
+}
+
+fn note(text: &str) -> Paragraph {
+    Paragraph::new().add_run(Run::new().add_text(text).italic())
+}
+
+// Teacher's answer under a question, coloured like the manual's answer boxes
+fn answer(answer: &str, detail: &str) -> Paragraph {
+    Paragraph::new()
+        .indent(Some(LABEL_WIDTH), None, None, None)
+        .add_run(Run::new().add_text(answer).bold().color("0000FF"))
+        .add_run(Run::new().add_break(BreakType::TextWrapping))
+        .add_run(Run::new().add_text(detail).color("0000FF"))
+}
+
+fn letter(index: usize) -> char {
+    (b'a' + (index % 26) as u8) as char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn lesson_with_questions(count: usize) -> CompleteLessonContent {
+        let mut lesson: CompleteLessonContent =
+            serde_json::from_str(include_str!("../fixtures/template_lesson.json")).unwrap();
+        let mcqs: Vec<_> = (1..=count)
+            .map(|i| {
+                json!({
+                    "question": format!("Revision question {}", i),
+                    "option_a": "Stone",
+                    "option_b": "Goat",
+                    "option_c": "Chair",
+                    "correct_answer": "B",
+                    "explanation": "",
+                })
+            })
+            .collect();
+        lesson.mcq_questions = serde_json::from_value(json!(mcqs)).unwrap();
+        lesson
+    }
+
+    fn document_xml(class_year: &str, mode: &str, lesson: CompleteLessonContent) -> String {
+        let options = ManualOptions {
+            subject_name: "Basic Science",
+            class_year,
+            mode,
+            question_bank: false,
+            ca_tests: false,
+        };
+        let input = template_input(
+            vec![lesson],
+            None,
+            Vec::new(),
+            SchoolBranding {
+                logo_tag: None,
+                watermark_tag: None,
+                ..Default::default()
+            },
+            &options,
+        );
+        String::from_utf8(render_manual(&input).build().document).unwrap()
+    }
+
+    #[test]
+    fn title_uses_the_manuals_year_label() {
+        let xml = document_xml("PRIMARY_3", "pupil", lesson_with_questions(1));
+        assert!(xml.contains("BASIC SCIENCE MANUAL (YEAR 3)"));
+        assert!(!xml.contains("PRIMARY 3"));
+    }
+
+    #[test]
+    fn shows_as_many_questions_as_the_printed_manual() {
+        let xml = document_xml("PRIMARY_3", "pupil", lesson_with_questions(5));
+        assert!(xml.contains("Revision question 3"));
+        assert!(!xml.contains("Revision question 4"));
+
+        let xml = document_xml("PRIMARY_3", "teacher", lesson_with_questions(5));
+        assert!(xml.contains("Revision question 5"));
+    }
+
+    #[test]
+    fn lesson_markup_is_formatted_not_printed() {
+        let xml = document_xml("PRIMARY_3", "pupil", lesson_with_questions(1));
+        assert!(xml.contains("₦25,000 * 2"));
+        assert!(!xml.contains("#strong"));
+        assert!(!xml.contains("\\*"));
+    }
+
+    #[test]
+    fn rejects_unknown_modes() {
+        let err = docx_engine(
+            vec![lesson_with_questions(1)],
+            "Basic Science",
+            "PRIMARY_3",
+            "exam",
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.code, "INVALID_MODE");
+    }
+}
@@ -0,0 +1,631 @@
+// HTML version of a manual for the school intranet and phones. It is built from the
+// same TemplateInput as the PDF, so lessons, translations and question counts match
+// the printed manual; only the layout differs. Lesson text is typst markup at this
+// point and markup_to_html turns it into HTML.
+
+use baml_client::models::CompleteLessonContent;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use super::{
+    template_input, Lesson, ManualTranslation, SubPoint, TemplateInput, TypstContentSection,
+    TypstFigure,
+};
+use crate::utils::manual_strings::ManualStrings;
+use crate::utils::markup::{escape_html, markup_to_html};
+use crate::utils::pdf_cache::ManualOptions;
+use crate::utils::LessonAsset;
+use crate::{AgentError, SchoolBranding};
+
+pub fn html_engine(
+    lessons: Vec<CompleteLessonContent>,
+    subject_name: &str,
+    class_year: &str,
+    mode: &str, // "pupil" or "teacher"
+    translation: Option<ManualTranslation>,
+    assets: Vec<LessonAsset>,
+    branding: SchoolBranding,
+) -> Result<Vec<u8>, AgentError> {
+    if mode != "pupil" && mode != "teacher" {
+        return Err(AgentError {
+            message: format!(
+                "HTML export supports pupil and teacher modes, got '{}'",
+                mode
+            ),
+            code: "INVALID_MODE".to_string(),
+        });
+    }
+    if lessons.is_empty() {
+        return Err(AgentError {
+            message: format!("No lessons found for {} {}", subject_name, class_year),
+            code: "NO_LESSONS".to_string(),
+        });
+    }
+    let options = ManualOptions {
+        subject_name,
+        class_year,
+        mode,
+        question_bank: false,
+        ca_tests: false,
+    };
+    let input = template_input(lessons, translation, assets, branding, &options);
+    Ok(render_manual(&input).into_bytes())
+}
+
+fn render_manual(input: &TemplateInput) -> String {
+    let strings = &input.strings;
+    let branding = &input.branding;
+    let teacher = input.mode == "teacher";
+    let title = format!(
+        "{} {} ({} {})",
+        input.subject_name, strings.manual_label, strings.year_label, input.class_year
+    );
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape_html(&title)));
+    html.push_str(&format!(
+        "<style>\n{}</style>\n</head>\n<body>\n",
+        STYLE
+            .replace("{primary}", &branding.primary_colour)
+            .replace("{accent}", &branding.accent_colour)
+    ));
+
+    // School header
+    html.push_str("<header>\n");
+    if let (Some(logo), Some(content_type)) = (&branding.logo, &branding.logo_content_type) {
+        html.push_str(&format!(
+            "<img class=\"logo\" src=\"{}\" alt=\"\">\n",
+            data_uri(logo, content_type)
+        ));
+    }
+    html.push_str(&format!(
+        "<p class=\"school\">{}</p>\n<p class=\"motto\">{}</p>\n<h1>{}</h1>\n</header>\n",
+        escape_html(&branding.name),
+        escape_html(&branding.motto),
+        escape_html(&title.to_uppercase())
+    ));
+
+    // Contents, linking to each lesson
+    html.push_str(&format!(
+        "<nav>\n<h2>{}</h2>\n<ul>\n",
+        escape_html(&strings.table_of_contents)
+    ));
+    for (i, lesson) in input.lessons.iter().enumerate() {
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{} {}: {}</a></li>\n",
+            lesson_anchor(i, lesson),
+            escape_html(&strings.topic_label),
+            lesson.week,
+            markup_to_html(&lesson.topic_title)
+        ));
+    }
+    html.push_str("</ul>\n</nav>\n<main>\n");
+
+    let mut last_term: Option<&str> = None;
+    for (i, lesson) in input.lessons.iter().enumerate() {
+        if last_term != Some(lesson.term.as_str()) {
+            html.push_str(&format!(
+                "<h2 class=\"term\">{}</h2>\n",
+                escape_html(&term_name(strings, &lesson.term).to_uppercase())
+            ));
+            last_term = Some(lesson.term.as_str());
+        }
+        render_lesson(&mut html, input, i, lesson, teacher);
+    }
+
+    if !input.glossary_appendix.is_empty() {
+        html.push_str(&format!(
+            "<section class=\"appendix\">\n<h2>{}</h2>\n<dl>\n",
+            escape_html(&strings.glossary)
+        ));
+        for entry in &input.glossary_appendix {
+            html.push_str(&format!(
+                "<dt>{}{}</dt><dd>{}{}</dd>\n",
+                markup_to_html(&entry.term),
+                entry
+                    .translated_term
+                    .as_deref()
+                    .map(|t| format!(" ({})", markup_to_html(t)))
+                    .unwrap_or_default(),
+                markup_to_html(&entry.definition),
+                translation(&entry.translated_definition)
+            ));
+        }
+        html.push_str("</dl>\n</section>\n");
+    }
+
+    html.push_str("</main>\n</body>\n</html>\n");
+    html
+}
+
+fn render_lesson(
+    html: &mut String,
+    input: &TemplateInput,
+    index: usize,
+    lesson: &Lesson,
+    teacher: bool,
+) {
+    let strings = &input.strings;
+    html.push_str(&format!(
+        "<article id=\"{}\">\n<h2>{} {}: {}</h2>\n",
+        lesson_anchor(index, lesson),
+        escape_html(&strings.topic_label),
+        lesson.week,
+        markup_to_html(&lesson.topic_title)
+    ));
+    if let Some(translated) = &lesson.translated_topic_title {
+        html.push_str(&format!(
+            "<p class=\"tr\">{}</p>\n",
+            markup_to_html(translated)
+        ));
+    }
+
+    heading(html, &strings.lesson_objectives);
+    html.push_str("<ul>\n");
+    for objective in &lesson.objectives {
+        html.push_str(&format!(
+            "<li>{} ({}){}</li>\n",
+            markup_to_html(&objective.objective),
+            markup_to_html(&objective.taxonomy_level),
+            translation(&objective.translated_objective)
+        ));
+    }
+    html.push_str("</ul>\n");
+
+    if teacher {
+        heading(html, &strings.previous_knowledge);
+        list(html, "ul", &lesson.prior_knowledge);
+        heading(html, &strings.instructional_materials);
+        list(html, "ul", &lesson.materials);
+        heading(html, &strings.teacher_preparation);
+        html.push_str(&format!(
+            "<p><strong>{}</strong> {}<br><strong>{}</strong> {} {}</p>\n",
+            escape_html(&strings.materials_label),
+            lesson
+                .materials
+                .iter()
+                .map(|m| markup_to_html(m))
+                .collect::<Vec<_>>()
+                .join(", "),
+            escape_html(&strings.duration_label),
+            lesson.duration_mins,
+            escape_html(&strings.minutes)
+        ));
+    }
+
+    heading(html, &strings.lesson_content);
+    html.push_str(&format!(
+        "<p>{}{}</p>\n",
+        markup_to_html(&lesson.introduction),
+        translation(&lesson.translated_introduction)
+    ));
+    for section in &lesson.content_sections {
+        render_section(html, section);
+    }
+    html.push_str(&format!(
+        "<p><strong>{}</strong><br>{}{}</p>\n",
+        escape_html(&strings.conclusion),
+        markup_to_html(&lesson.conclusion),
+        translation(&lesson.translated_conclusion)
+    ));
+
+    heading(html, &strings.key_points);
+    html.push_str("<ol>\n");
+    for (i, key_point) in lesson.key_points.iter().enumerate() {
+        let translated = lesson
+            .translated_key_points
+            .as_ref()
+            .and_then(|t| t.get(i).cloned());
+        html.push_str(&format!(
+            "<li>{}{}</li>\n",
+            markup_to_html(key_point),
+            translation(&translated)
+        ));
+    }
+    html.push_str("</ol>\n");
+
+    if !teacher && !lesson.glossary.is_empty() {
+        html.push_str(&format!(
+            "<aside class=\"new-words\">\n<strong>{}</strong>\n<dl>\n",
+            escape_html(&strings.new_words)
+        ));
+        for entry in &lesson.glossary {
+            html.push_str(&format!(
+                "<dt>{}{}</dt><dd>{}{}</dd>\n",
+                markup_to_html(&entry.term),
+                entry
+                    .translated_term
+                    .as_deref()
+                    .map(|t| format!(" ({})", markup_to_html(t)))
+                    .unwrap_or_default(),
+                markup_to_html(&entry.definition),
+                translation(&entry.translated_definition)
+            ));
+        }
+        html.push_str("</dl>\n</aside>\n");
+    }
+
+    if teacher && !lesson.lesson_steps.is_empty() {
+        heading(html, &strings.lesson_steps);
+        html.push_str(&format!(
+            "<div class=\"table\"><table>\n<tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>\n",
+            escape_html(&strings.step_label),
+            escape_html(&strings.phase_label),
+            escape_html(&strings.teacher_actions),
+            escape_html(&strings.pupil_activities)
+        ));
+        for step in &lesson.lesson_steps {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                step.step_number,
+                markup_to_html(&step.phase),
+                markup_to_html(&step.teacher_actions),
+                markup_to_html(&step.pupil_activities)
+            ));
+        }
+        html.push_str("</table></div>\n");
+    }
+
+    if teacher {
+        heading(html, &strings.formative_assessment);
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            markup_to_html(&lesson.formative_assessment)
+        ));
+        heading(html, &strings.summative_assessment);
+        html.push_str(&format!(
+            "<p>{}</p>\n",
+            markup_to_html(&lesson.summative_assessment)
+        ));
+        heading(html, &strings.success_criteria);
+        list(html, "ul", &lesson.success_criteria);
+    }
+
+    let mcq_count = (input.question_counts.mcq.max(0) as usize).min(lesson.mcq_questions.len());
+    if mcq_count > 0 {
+        heading(html, &strings.revision_questions);
+        html.push_str("<ol class=\"questions\">\n");
+        for q in &lesson.mcq_questions[..mcq_count] {
+            html.push_str(&format!(
+                "<li>{}{}<br>(a) {} (b) {} (c) {}",
+                markup_to_html(&q.question),
+                translation(&q.translated_question),
+                markup_to_html(&q.option_a),
+                markup_to_html(&q.option_b),
+                markup_to_html(&q.option_c)
+            ));
+            if let Some(options) = &q.translated_options {
+                let options: Vec<String> = options.iter().map(|o| markup_to_html(o)).collect();
+                if let [a, b, c] = options.as_slice() {
+                    html.push_str(&format!(
+                        "<span class=\"tr\">(a) {} (b) {} (c) {}</span>",
+                        a, b, c
+                    ));
+                }
+            }
+            if teacher {
+                html.push_str(&format!(
+                    "<p class=\"answer\">[{} {}]<br>{} {}</p>",
+                    escape_html(&strings.answer_label),
+                    escape_html(&q.correct_answer),
+                    escape_html(&strings.explanation_label),
+                    markup_to_html(&q.explanation)
+                ));
+            }
+            html.push_str("</li>\n");
+        }
+        html.push_str("</ol>\n");
+    }
+
+    let theory_count =
+        (input.question_counts.theory.max(0) as usize).min(lesson.theoretical_questions.len());
+    if theory_count > 0 {
+        heading(html, &strings.theoretical_questions);
+        html.push_str("<ol class=\"questions\">\n");
+        for q in &lesson.theoretical_questions[..theory_count] {
+            html.push_str(&format!(
+                "<li>{}{}",
+                markup_to_html(&q.question),
+                translation(&q.translated_question)
+            ));
+            for part in &q.parts {
+                html.push_str(&format!("<br>{}", markup_to_html(part)));
+            }
+            for part in q.translated_parts.iter().flatten() {
+                html.push_str(&format!(
+                    "<span class=\"tr\">{}</span>",
+                    markup_to_html(part)
+                ));
+            }
+            if teacher {
+                html.push_str(&format!(
+                    "<p class=\"answer\">{} {}</p>",
+                    escape_html(&strings.answer_label),
+                    markup_to_html(&q.model_answer)
+                ));
+            }
+            html.push_str("</li>\n");
+        }
+        html.push_str("</ol>\n");
+    }
+
+    if teacher {
+        heading(html, &strings.extension_activities);
+        list(html, "ul", &lesson.extension_activities);
+        heading(html, &strings.remediation);
+        html.push_str(&format!("<p>{}</p>\n", markup_to_html(&lesson.remediation)));
+        heading(html, &strings.references);
+        list(html, "ul", &lesson.textbook_references);
+    }
+
+    html.push_str("</article>\n");
+}
+
+fn render_section(html: &mut String, section: &TypstContentSection) {
+    html.push_str(&format!(
+        "<section>\n<h4>{}. {}{}</h4>\n<p>{}{}</p>\n",
+        section.section_number,
+        markup_to_html(&section.header),
+        translation(&section.translated_header),
+        markup_to_html(&section.body),
+        translation(&section.translated_body)
+    ));
+
+    for figure in &section.figures {
+        render_figure(html, figure);
+    }
+
+    if let Some(image) = &section.image {
+        let caption = image
+            .caption
+            .as_deref()
+            .map(|c| format!("<figcaption>{}</figcaption>", markup_to_html(c)))
+            .unwrap_or_default();
+        match (&image.data, &image.content_type) {
+            (Some(data), Some(content_type)) => html.push_str(&format!(
+                "<figure><img src=\"{}\" alt=\"{}\">{}</figure>\n",
+                data_uri(data, content_type),
+                escape_html(&image.tag),
+                caption
+            )),
+            // Placeholder until an image with this tag is added to the library
+            _ => html.push_str(&format!(
+                "<figure><div class=\"placeholder\">{}</div>{}</figure>\n",
+                escape_html(&image.tag),
+                caption
+            )),
+        }
+    }
+
+    for table in &section.tables {
+        html.push_str(&format!(
+            "<figure><figcaption>{}</figcaption><div class=\"table\"><table>\n<tr>",
+            markup_to_html(&table.caption)
+        ));
+        for header in &table.headers {
+            html.push_str(&format!("<th>{}</th>", markup_to_html(header)));
+        }
+        html.push_str("</tr>\n");
+        for row in &table.rows {
+            html.push_str("<tr>");
+            for cell in row {
+                html.push_str(&format!("<td>{}</td>", markup_to_html(cell)));
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table></div></figure>\n");
+    }
+
+    if let Some(sub_points) = &section.sub_points {
+        render_sub_points(html, sub_points);
+    }
+    html.push_str("</section>\n");
+}
+
+fn render_sub_points(html: &mut String, sub_points: &[SubPoint]) {
+    html.push_str("<ul class=\"sub-points\">\n");
+    for sub_point in sub_points {
+        let point = &sub_point.text;
+        html.push_str(&format!(
+            "<li><span class=\"marker\">{}</span> ",
+            markup_to_html(&sub_point.sub_number)
+        ));
+        if let Some(header) = &point.header {
+            html.push_str(&format!("<strong>{}</strong><br>", markup_to_html(header)));
+        }
+        html.push_str(&markup_to_html(&point.body));
+        html.push_str(&translation(&point.translated_body));
+        if let Some(nested) = point.sub_points.as_deref().filter(|sps| !sps.is_empty()) {
+            render_sub_points(html, nested);
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n");
+}
+
+// Figures are drawn by typst in the PDF; on the web the data is shown as text
+fn render_figure(html: &mut String, figure: &TypstFigure) {
+    let body = match figure.kind.as_str() {
+        "SHAPE" => {
+            let labels: Vec<String> = figure
+                .side_labels
+                .iter()
+                .map(|l| markup_to_html(l))
+                .collect();
+            format!(
+                "<p>{} {}</p>",
+                escape_html(&figure.shape),
+                labels.join(", ")
+            )
+        }
+        "NUMBER_LINE" => {
+            let numbers: Vec<String> = (figure.start..=figure.end)
+                .step_by(figure.step.max(1) as usize)
+                .map(|n| {
+                    if figure.marks.contains(&n) {
+                        format!("<strong>{}</strong>", n)
+                    } else {
+                        n.to_string()
+                    }
+                })
+                .collect();
+            format!("<p class=\"number-line\">{}</p>", numbers.join(" — "))
+        }
+        "BAR_CHART" => {
+            let rows: Vec<String> = figure
+                .categories
+                .iter()
+                .zip(&figure.values)
+                .map(|(category, value)| {
+                    format!(
+                        "<tr><td>{}</td><td>{}</td></tr>",
+                        markup_to_html(category),
+                        value
+                    )
+                })
+                .collect();
+            format!(
+                "<div class=\"table\"><table><tr><th></th><th>{}</th></tr>{}</table></div>",
+                figure
+                    .y_label
+                    .as_deref()
+                    .map(markup_to_html)
+                    .unwrap_or_default(),
+                rows.join("")
+            )
+        }
+        "CLOCK" => format!(
+            "<p class=\"clock\">{}:{:02}</p>",
+            figure.hour, figure.minute
+        ),
+        _ => String::new(),
+    };
+    html.push_str(&format!(
+        "<figure>{}<figcaption>{}</figcaption></figure>\n",
+        body,
+        markup_to_html(&figure.caption)
+    ));
+}
+
+fn heading(html: &mut String, text: &str) {
+    html.push_str(&format!("<h3>{}</h3>\n", escape_html(text)));
+}
+
+fn list(html: &mut String, tag: &str, items: &[String]) {
+    html.push_str(&format!("<{}>\n", tag));
+    for item in items {
+        html.push_str(&format!("<li>{}</li>\n", markup_to_html(item)));
+    }
+    html.push_str(&format!("</{}>\n", tag));
+}
+
+// Translation printed under the English text in bilingual manuals
+fn translation(text: &Option<String>) -> String {
+    match text {
+        Some(text) => format!("<span class=\"tr\">{}</span>", markup_to_html(text)),
+        None => String::new(),
+    }
+}
+
+pub(super) fn term_name(strings: &ManualStrings, term: &str) -> String {
+    match term {
+        "FIRST" => strings.first_term.clone(),
+        "SECOND" => strings.second_term.clone(),
+        "THIRD" => strings.third_term.clone(),
+        term => format!("{} TERM", term),
+    }
+}
+
+// A manual can hold two lessons for the same week, so the position keeps anchors unique
+fn lesson_anchor(index: usize, lesson: &Lesson) -> String {
+    format!(
+        "lesson-{}-{}-week-{}",
+        index + 1,
+        lesson.term.to_lowercase(),
+        lesson.week
+    )
+}
+
+// Images are inlined so the page works as a single file. The content type is the one the
+// asset was uploaded with, or the library file's extension, so GIFs and WebPs show too.
+fn data_uri(data: &[u8], content_type: &str) -> String {
+    format!(
+        "data:{};base64,{}",
+        escape_html(content_type),
+        BASE64.encode(data)
+    )
+}
+
+const STYLE: &str = "body { font-family: 'Times New Roman', serif; max-width: 46rem; margin: 0 auto; padding: 1rem; line-height: 1.5; }
+header { text-align: center; border-bottom: 2px solid {accent}; margin-bottom: 1rem; }
+.logo { max-height: 4rem; }
+.school { font-weight: bold; font-size: 1.2rem; margin: 0.2rem 0; }
+.motto { font-style: italic; margin: 0; }
+h1, h2, h3, h4 { color: {primary}; }
+article > h2 { text-transform: uppercase; }
+h2.term { text-align: center; border-top: 2px solid {accent}; padding-top: 1rem; }
+.tr { display: block; font-style: italic; color: #505050; }
+.sub-points { list-style: none; padding-left: 1.2rem; }
+.marker { display: inline-block; min-width: 2.4em; }
+figure { margin: 1rem 0; text-align: center; }
+figure img { max-width: 100%; }
+figcaption { font-style: italic; }
+.placeholder { border: 1px dashed gray; color: gray; padding: 3rem 0; }
+.table { overflow-x: auto; }
+table { border-collapse: collapse; width: 100%; text-align: left; }
+th, td { border: 1px solid #999; padding: 0.3rem; vertical-align: top; }
+th { background: #f0f0f0; }
+.new-words { border: 1px solid {accent}; background: #f5f5f5; border-radius: 4px; padding: 0.6rem; }
+.answer { color: blue; font-weight: bold; }
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(lessons: Vec<CompleteLessonContent>) -> String {
+        let options = ManualOptions {
+            subject_name: "Basic Science",
+            class_year: "PRIMARY_3",
+            mode: "pupil",
+            question_bank: false,
+            ca_tests: false,
+        };
+        let branding = SchoolBranding {
+            logo_tag: None,
+            watermark_tag: None,
+            ..Default::default()
+        };
+        render_manual(&template_input(
+            lessons,
+            None,
+            Vec::new(),
+            branding,
+            &options,
+        ))
+    }
+
+    #[test]
+    fn lessons_of_the_same_week_get_their_own_anchor() {
+        let lesson: CompleteLessonContent =
+            serde_json::from_str(include_str!("../fixtures/template_lesson.json")).unwrap();
+        let html = manual(vec![lesson.clone(), lesson]);
+        assert!(html.contains("<a href=\"#lesson-1-first-week-1\">"));
+        assert!(html.contains("<a href=\"#lesson-2-first-week-1\">"));
+        assert!(html.contains("<article id=\"lesson-2-first-week-1\">"));
+    }
+
+    #[test]
+    fn data_uri_uses_the_images_content_type() {
+        assert_eq!(
+            data_uri(b"GIF89a", "image/gif"),
+            "data:image/gif;base64,R0lGODlh"
+        );
+        assert_eq!(
+            data_uri(b"", "image/png\" onerror=\"x"),
+            "data:image/png&quot; onerror=&quot;x;base64,"
+        );
+    }
+}
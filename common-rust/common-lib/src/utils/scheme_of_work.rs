@@ -0,0 +1,138 @@
+use baml_client::models::CompleteLessonContent;
+use derive_typst_intoval::{IntoDict, IntoValue};
+use typst::foundations::{Dict, IntoValue};
+
+use crate::utils::markup::{sanitize, sanitize_all};
+use crate::utils::pdf_engine::{
+    class_level_to_string, class_year_number, compile_pdf, term_to_string,
+};
+use crate::utils::{subject_matches, topic_class_level, topic_term};
+use crate::{AgentError, TopicRecord};
+
+// File paths - these should be in your Golem agent's filesystem
+static SCHEME_OF_WORK_TEMPLATE_PATH: &str = "/templates/scheme_of_work.typ";
+
+pub fn scheme_of_work_engine(
+    lessons: Vec<CompleteLessonContent>,
+    topics: Vec<TopicRecord>,
+    subject_name: &str,
+    class_year: &str, // "PRIMARY_1" ... "JSS_3"
+    school_name: &str,
+) -> Result<Vec<u8>, AgentError> {
+    let mut weeks: Vec<SchemeWeek> = lessons
+        .into_iter()
+        .map(|lesson| SchemeWeek {
+            term: term_to_string(lesson.term),
+            week: lesson.week,
+            topic_title: sanitize(&lesson.topic_title),
+            objectives: lesson
+                .objectives
+                .iter()
+                .map(|o| sanitize(&o.objective))
+                .collect::<Vec<_>>()
+                .join("; "),
+            materials: sanitize_all(lesson.materials).join(", "),
+            context: String::new(),
+            generated: true,
+        })
+        .collect();
+
+    // Weeks without a generated lesson still appear, using the planned topic. Topics are
+    // matched to the subject by the same rule the lessons were fetched with.
+    for topic in topics {
+        if !subject_matches(&topic.subject, subject_name) {
+            continue;
+        }
+        let (Ok(class_level), Ok(term)) = (
+            topic_class_level(topic.class.as_str()),
+            topic_term(topic.term.as_str()),
+        ) else {
+            println!(
+                "⚠️  Skipping topic '{}' with unknown class or term",
+                topic.topic
+            );
+            continue;
+        };
+        if class_level_to_string(class_level) != class_year {
+            continue;
+        }
+        let term = term_to_string(term);
+        if weeks.iter().any(|w| w.term == term && w.week == topic.week) {
+            continue;
+        }
+        weeks.push(SchemeWeek {
+            term,
+            week: topic.week,
+            topic_title: sanitize(&topic.topic),
+            // Planned topics carry free-text context, not objectives
+            objectives: String::new(),
+            materials: String::new(),
+            context: sanitize(&topic.context.unwrap_or_default()),
+            generated: false,
+        });
+    }
+    weeks.sort_by_key(|w| (term_order(&w.term), w.week));
+
+    let mut terms: Vec<SchemeTerm> = Vec::new();
+    for week in weeks {
+        match terms.last_mut() {
+            Some(entry) if entry.term == week.term => entry.weeks.push(week),
+            _ => terms.push(SchemeTerm {
+                term: week.term.clone(),
+                weeks: vec![week],
+            }),
+        }
+    }
+
+    let input = SchemeOfWorkInput {
+        school_name: school_name.to_string(),
+        subject_name: subject_name.to_string(),
+        class_year: class_year_number(class_year).to_string(),
+        class_level: class_year.replace('_', " "),
+        terms,
+    };
+
+    compile_pdf(SCHEME_OF_WORK_TEMPLATE_PATH, input)
+}
+
+// Input structure matching scheme_of_work.typ expectations
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct SchemeOfWorkInput {
+    school_name: String,
+    subject_name: String,
+    class_year: String,
+    class_level: String,
+    terms: Vec<SchemeTerm>,
+}
+
+impl From<SchemeOfWorkInput> for Dict {
+    fn from(value: SchemeOfWorkInput) -> Self {
+        value.into_dict()
+    }
+}
+
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct SchemeTerm {
+    term: String,
+    weeks: Vec<SchemeWeek>,
+}
+
+#[derive(Debug, Clone, IntoValue, IntoDict)]
+struct SchemeWeek {
+    term: String,
+    week: i32,
+    topic_title: String,
+    objectives: String,
+    materials: String,
+    context: String, // notes from the planned topic, empty for generated lessons
+    generated: bool, // false when only the planned topic exists
+}
+
+fn term_order(term: &str) -> u8 {
+    match term {
+        "FIRST" => 1,
+        "SECOND" => 2,
+        "THIRD" => 3,
+        _ => 4,
+    }
+}
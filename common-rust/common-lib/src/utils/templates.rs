@@ -0,0 +1,179 @@
+// Named typst templates for manuals. The built-in templates ship in the component
+// filesystem as version 0; schools upload their own, or newer versions of a built-in,
+// which are checked against a fixture lesson before they are stored. The newest
+// version of a name is the active one, "name@version" pins an older one.
+
+use baml_client::models::CompleteLessonContent;
+use serde::Deserialize;
+use std::fs;
+
+use crate::utils::pdf_cache::ManualOptions;
+use crate::utils::pdf_engine::pdf_engine;
+use crate::utils::{create_template_row, fetch_template, is_valid_asset_tag, Tenant};
+use crate::{AgentError, SchoolBranding};
+
+static TEMPLATE_PATH: &str = "/templates/template.typ";
+static LESSON_NOTE_TEMPLATE_PATH: &str = "/templates/lesson_note.typ";
+
+const BUILTIN_TEMPLATES: &[(&str, &str)] = &[
+    ("full-manual", TEMPLATE_PATH),
+    ("lesson-note", LESSON_NOTE_TEMPLATE_PATH),
+];
+
+// Every template has to render all modes of the fixture, since any of them can be requested
+const FIXTURE_MODES: &[&str] = &["pupil", "teacher", "lesson-note"];
+const FIXTURE_LESSON: &str = include_str!("fixtures/template_lesson.json");
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManualTemplate {
+    pub name: String,
+    pub version: u32,
+    pub source: String,
+}
+
+// Lesson notes follow the ministry layout rather than the manual template
+pub fn default_template_name(mode: &str) -> &'static str {
+    match mode {
+        "lesson-note" => "lesson-note",
+        _ => "full-manual",
+    }
+}
+
+pub fn builtin_template_names() -> Vec<String> {
+    BUILTIN_TEMPLATES
+        .iter()
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+// "" picks the default for the mode, "exam-paper" the active version, "exam-paper@2" a pinned one
+pub async fn resolve_template(
+    tenant: &Tenant,
+    selection: &str,
+    mode: &str,
+) -> Result<ManualTemplate, AgentError> {
+    let selection = match selection.trim() {
+        "" => default_template_name(mode),
+        selection => selection,
+    };
+    let (name, version) = parse_template_selection(selection)?;
+
+    if let Some(template) = fetch_template(tenant, name, version).await? {
+        return Ok(template);
+    }
+    match BUILTIN_TEMPLATES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+    {
+        Some((_, path)) if version.unwrap_or(0) == 0 => {
+            let source = fs::read_to_string(path).map_err(|e| AgentError {
+                message: format!("Could not read template file: {}", e),
+                code: "TEMPLATE_READ_ERROR".to_string(),
+            })?;
+            Ok(ManualTemplate {
+                name: name.to_string(),
+                version: 0,
+                source,
+            })
+        }
+        _ => Err(AgentError {
+            message: format!("Unknown template '{}'", selection),
+            code: "UNKNOWN_TEMPLATE".to_string(),
+        }),
+    }
+}
+
+// Store a new version of a template once it renders the fixture lesson in every mode
+pub async fn register_template(
+    tenant: &Tenant,
+    name: &str,
+    source: String,
+) -> Result<String, AgentError> {
+    if !is_valid_asset_tag(name) {
+        return Err(AgentError {
+            message: format!(
+                "Invalid template name '{}', use lowercase letters, digits and hyphens",
+                name
+            ),
+            code: "INVALID_TEMPLATE_NAME".to_string(),
+        });
+    }
+    let template = ManualTemplate {
+        name: name.to_string(),
+        version: 0,
+        source,
+    };
+    check_template(&template)?;
+
+    let version = create_template_row(tenant, name, &template.source).await?;
+    let response = format!("Activated template {}@{}", name, version);
+    println!("✓ {}", response);
+    Ok(response)
+}
+
+fn check_template(template: &ManualTemplate) -> Result<(), AgentError> {
+    let lesson: CompleteLessonContent =
+        serde_json::from_str(FIXTURE_LESSON).map_err(|e| AgentError {
+            message: format!("Failed to read fixture lesson: {:?}", e),
+            code: "DESERIALIZE_ERROR".to_string(),
+        })?;
+    for mode in FIXTURE_MODES {
+        let options = ManualOptions {
+            subject_name: &lesson.subject,
+            class_year: "PRIMARY_3",
+            mode,
+            question_bank: true,
+            ca_tests: true,
+        };
+        pdf_engine(
+            vec![lesson.clone()],
+            None,
+            Vec::new(),
+            SchoolBranding::default(),
+            template,
+            &options,
+        )
+        .map_err(|e| AgentError {
+            message: format!(
+                "Template '{}' failed on the {} fixture: {}",
+                template.name, mode, e.message
+            ),
+            code: "TEMPLATE_VALIDATION_ERROR".to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+fn parse_template_selection(selection: &str) -> Result<(&str, Option<u32>), AgentError> {
+    match selection.split_once('@') {
+        None => Ok((selection, None)),
+        Some((name, version)) => match version.parse::<u32>() {
+            Ok(version) => Ok((name, Some(version))),
+            Err(_) => Err(AgentError {
+                message: format!("Invalid template version in '{}'", selection),
+                code: "INVALID_TEMPLATE_VERSION".to_string(),
+            }),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_pins_a_version_with_at() {
+        assert_eq!(
+            parse_template_selection("full-manual").unwrap(),
+            ("full-manual", None)
+        );
+        assert_eq!(
+            parse_template_selection("full-manual@3").unwrap(),
+            ("full-manual", Some(3))
+        );
+        for selection in ["full-manual@x", "full-manual@", "full-manual@-1"] {
+            let err = parse_template_selection(selection).unwrap_err();
+            assert_eq!(err.code, "INVALID_TEMPLATE_VERSION", "{}", selection);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+// --- CONFIGURATION FROM INPUT ---
+#import sys: inputs
+
+#let school_name = inputs.at("school_name", default: "")
+
+#let subject_name = inputs.at("subject_name", default: "")
+#let class_year = inputs.at("class_year", default: "")
+#let term = inputs.at("term", default: "")
+#let options = inputs.at("options", default: ("A", "B", "C"))
+#let candidate_number_digits = inputs.at("candidate_number_digits", default: 6)
+#let questions = inputs.at("questions", default: ())
+
+// Question text is sanitized typst markup, see utils::markup
+#let md(text) = eval(text, mode: "markup")
+
+// --- TEMPLATE DEFINITION ---
+#let answer_sheet(
+  school: "",
+  subject: "",
+  year: "",
+  term: "",
+  options: (),
+  digits: 6,
+  questions: (),
+) = {
+  set document(title: subject + " Answer Sheet", author: school)
+  set text(font: "Linux Libertine", size: 11pt)
+  set page(paper: "a4", margin: (x: 0.6in, y: 0.6in))
+
+  let rows_per_column = 25
+  let answers = questions.map(q => q.answer)
+  let bubble_radius = 5.5pt
+
+  // A single answer bubble, filled solid on the teacher key
+  let bubble(label, filled: false) = circle(
+    radius: bubble_radius,
+    stroke: 0.7pt + black,
+    fill: if filled { black } else { none },
+    inset: 0pt,
+  )[#align(center + horizon, text(7pt, fill: if filled { white } else { black })[#label])]
+
+  // Timing marks down both edges so scanners can align the sheet
+  let timing_marks = {
+    place(top + left, dx: -0.4in, stack(dir: ttb, spacing: 2.2cm, ..range(12).map(_ => rect(width: 8pt, height: 4pt, fill: black))))
+    place(top + right, dx: 0.4in, stack(dir: ttb, spacing: 2.2cm, ..range(12).map(_ => rect(width: 8pt, height: 4pt, fill: black))))
+  }
+
+  let title = [
+    #text(16pt, weight: "bold")[#upper(school)] \
+    #text(13pt, weight: "bold")[#upper(subject) #if year != "" [(YEAR #year)] - #upper(term) TERM EXAMINATION] \
+  ]
+
+  // The composed paper the answer sheet and key are numbered against
+  let question_paper = {
+    align(center)[
+      #title
+      #text(12pt, weight: "bold")[OBJECTIVE QUESTIONS]
+    ]
+    v(0.4em)
+    text(9pt)[*Instructions:* Answer all questions. Shade the letter of the correct option on your answer sheet.]
+    v(0.6em)
+    for (i, q) in questions.enumerate() {
+      block(breakable: false, below: 1em)[
+        *#(i + 1).* #md(q.question) \
+        #h(1.6em)
+        #for (label, option) in ("A", "B", "C").zip(q.options) [
+          (#label) #md(option) #h(1.5em)
+        ]
+      ]
+    }
+  }
+
+  // Where each keyed answer comes from, for checking the paper against the lessons
+  let key_references = {
+    align(center)[
+      #title
+      #text(12pt, weight: "bold")[MARKING KEY REFERENCES]
+    ]
+    v(0.6em)
+    table(
+      columns: (auto, auto, auto, 1fr),
+      stroke: 0.5pt,
+      inset: 4pt,
+      table.header([*No.*], [*Answer*], [*Week*], [*Topic*]),
+      ..questions
+        .enumerate()
+        .map(((i, q)) => ([#(i + 1)], [#q.answer], [#q.week], [#md(q.topic_title)]))
+        .flatten()
+    )
+  }
+
+  let sheet(key: false) = {
+    timing_marks
+
+    // 1. HEADER
+    align(center)[
+      #title
+      #text(12pt, weight: "bold")[#if key [TEACHER MARKING KEY] else [OBJECTIVE ANSWER SHEET]]
+    ]
+    v(0.6em)
+
+    // 2. CANDIDATE DETAILS
+    grid(
+      columns: (1fr, auto),
+      column-gutter: 1.5em,
+      [
+        *Candidate Name:* \
+        #box(width: 100%, height: 0.9cm, stroke: 0.7pt)
+        #v(0.4em)
+        *Class:* #box(width: 4cm, height: 0.7cm, stroke: 0.7pt) #h(1em)
+        *Date:* #box(width: 4cm, height: 0.7cm, stroke: 0.7pt)
+        #v(0.6em)
+        #text(9pt)[
+          *Instructions:* Use a black pencil. Fill each bubble completely.
+          Shade only one option per question. Erase changes cleanly.
+        ]
+      ],
+      [
+        *Candidate Number* \
+        #table(
+          columns: range(digits).map(_ => auto),
+          align: center,
+          stroke: 0.5pt,
+          inset: 3pt,
+          ..range(digits).map(_ => box(width: 12pt, height: 12pt)),
+          ..range(10).map(d => range(digits).map(_ => bubble(str(d)))).flatten()
+        )
+      ],
+    )
+    v(0.8em)
+    line(length: 100%, stroke: 1pt)
+    v(0.6em)
+
+    // 3. ANSWER BUBBLES
+    let column_count = calc.ceil(answers.len() / rows_per_column)
+    grid(
+      columns: range(column_count).map(_ => 1fr),
+      ..range(column_count).map(c => {
+        let start = c * rows_per_column
+        let end = calc.min(start + rows_per_column, answers.len())
+        stack(
+          dir: ttb,
+          spacing: 4pt,
+          ..range(start, end).map(i => {
+            box(width: 1.6em, align(right, text(weight: "bold")[#(i + 1).]))
+            h(0.4em)
+            for opt in options {
+              bubble(opt, filled: key and answers.at(i) == opt)
+              h(3pt)
+            }
+          })
+        )
+      })
+    )
+  }
+
+  // Question paper and pupil sheet first, then the teacher overlay key on the same
+  // layout and the lesson each answer comes from
+  question_paper
+  pagebreak()
+  sheet(key: false)
+  pagebreak()
+  sheet(key: true)
+  pagebreak()
+  key_references
+}
+
+// --- EXECUTE ---
+#answer_sheet(
+  school: school_name,
+  subject: subject_name,
+  year: class_year,
+  term: term,
+  options: options,
+  digits: candidate_number_digits,
+  questions: questions,
+)
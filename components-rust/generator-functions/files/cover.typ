@@ -0,0 +1,88 @@
+// --- CONFIGURATION FROM INPUT ---
+#import sys: inputs
+
+#let subject_name = inputs.at("subject_name", default: "")
+#let class_label = inputs.at("class_label", default: "")
+#let mode = inputs.at("mode", default: "pupil")
+#let edition = inputs.at("edition", default: "")
+#let subject_colour = inputs.at("subject_colour", default: "#000000")
+#let artwork = inputs.at("artwork", default: none)
+#let branding = inputs.at("branding")
+
+// --- TEMPLATE DEFINITION ---
+#let cover(
+  subject: "",
+  class_label: "",
+  mode: "pupil",
+  edition: "",
+  colour: "#000000",
+  artwork: none,
+  branding: (:),
+) = {
+  let colour = rgb(colour)
+  let accent = rgb(branding.accent_colour)
+
+  set document(title: subject + " " + class_label + " Cover", author: branding.name)
+  set text(font: "Linux Libertine", size: 12pt, fill: white)
+  set page(paper: "a4", margin: 0pt, fill: colour)
+
+  // Lighter band behind the artwork so photos and drawings read on any subject colour
+  let band_height = 48%
+
+  // 1. SCHOOL
+  block(width: 100%, inset: (x: 0.8in, top: 0.7in, bottom: 0.3in))[
+    #grid(
+      columns: (auto, 1fr),
+      column-gutter: 1em,
+      align: horizon,
+      if branding.logo != none {
+        box(fill: white, inset: 6pt, radius: 4pt, image(branding.logo, height: 1.6cm))
+      },
+      [
+        #text(18pt, weight: "bold")[#upper(branding.name)] \
+        #text(11pt, style: "italic")[#branding.motto]
+      ],
+    )
+  ]
+
+  // 2. ARTWORK
+  block(width: 100%, height: band_height, fill: white.transparentize(15%), inset: 0.5in)[
+    #align(center + horizon)[
+      #if artwork != none {
+        image(artwork, height: 100%, fit: "contain")
+      } else if subject != "" {
+        // No artwork uploaded yet, show the subject initial in the subject colour
+        text(160pt, weight: "bold", fill: colour)[#upper(subject.first())]
+      }
+    ]
+  ]
+
+  // 3. TITLE
+  block(width: 100%, inset: (x: 0.8in, top: 0.4in))[
+    #text(40pt, weight: "bold")[#upper(subject)] \
+    #v(0.2em)
+    #box(fill: accent, inset: (x: 10pt, y: 6pt), radius: 3pt)[
+      #text(20pt, weight: "bold")[#upper(class_label)]
+    ]
+    #h(0.6em)
+    #text(16pt, weight: "bold")[#if mode == "teacher" [TEACHER'S MANUAL] else [PUPIL'S MANUAL]]
+  ]
+
+  // 4. EDITION
+  place(bottom + left, dx: 0.8in, dy: -0.6in)[
+    #text(12pt)[#if edition != "" [#edition Edition]]
+  ]
+  place(bottom + right, dx: -0.8in, dy: -0.6in)[
+    #text(12pt, weight: "bold")[#branding.short_name]
+  ]
+}
+
+#cover(
+  subject: subject_name,
+  class_label: class_label,
+  mode: mode,
+  edition: edition,
+  colour: subject_colour,
+  artwork: artwork,
+  branding: branding,
+)
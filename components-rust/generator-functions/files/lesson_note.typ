@@ -0,0 +1,115 @@
+// --- CONFIGURATION FROM INPUT ---
+#import sys: inputs
+
+#let subject_name = inputs.at("subject_name", default: "")
+#let class_year = inputs.at("class_year", default: "1")
+#let dummy_lessons = inputs.at("lessons", default: ())
+#let branding = inputs.at("branding")
+
+// Lesson text arrives as escaped typst markup (see utils/markup.rs), render it as such
+#let md(text) = eval(text, mode: "markup")
+
+// --- TEMPLATE DEFINITION ---
+#let lesson_notes(
+  branding: (:),
+  subject: "",
+  year: "",
+  lessons: (),
+) = {
+  set document(title: subject + " Lesson Notes", author: branding.name)
+  set text(font: "Linux Libertine", size: 12pt)
+  show math.equation: set text(font: "New Computer Modern Math")
+  set par(justify: true)
+  set page(
+    paper: "a4",
+    margin: (x: 0.8in, y: 0.9in),
+    footer: context { align(center, text(10pt)[#counter(page).display("1")]) }
+  )
+
+  // Bold label followed by its value on the same line
+  let field(label, value) = [*#label:* #value]
+
+  // Heading for each part of the note, numbered like the ministry form
+  let part(title) = {
+    v(0.4em)
+    text(weight: "bold")[#upper(title)]
+    linebreak()
+  }
+
+  for lesson in lessons {
+    align(center)[
+      #text(16pt, weight: "bold", fill: rgb(branding.primary_colour))[#upper(branding.name)] \
+      #text(13pt, weight: "bold")[WEEKLY LESSON NOTE]
+    ]
+    v(0.5em)
+
+    // 1. HEADER DETAILS
+    table(
+      columns: (1fr, 1fr),
+      stroke: 0.5pt,
+      inset: 6pt,
+      field("Date", ""), field("Week", str(lesson.week)),
+      field("Term", lesson.term + " TERM"), field("Class", lesson.class_level.replace("_", " ")),
+      field("Subject", lesson.subject), field("Duration", str(lesson.duration_mins) + " mins"),
+      table.cell(colspan: 2, field("Topic", upper(md(lesson.topic_title)))),
+    )
+
+    // 2. BEHAVIOURAL OBJECTIVES
+    part("Behavioural Objectives")
+    [At the end of the lesson, pupils should be able to:]
+    enum(..lesson.objectives.map(it => md(it.objective)))
+
+    // 3. INSTRUCTIONAL MATERIALS
+    part("Instructional Materials")
+    list(..lesson.materials.map(md))
+
+    // 4. PREVIOUS KNOWLEDGE
+    part("Previous Knowledge")
+    list(..lesson.prior_knowledge.map(md))
+
+    // 5. PRESENTATION
+    part("Presentation")
+    if lesson.lesson_steps.len() > 0 {
+      table(
+        columns: (auto, 1.5fr, 1.5fr),
+        fill: (col, row) => if row == 0 { silver.lighten(60%) },
+        [*Step*], [*Teacher's Activities*], [*Pupils' Activities*],
+        ..lesson.lesson_steps.map(s => (
+          [#s.step_number \ #text(9pt)[#md(s.phase) (#s.duration_mins mins)]],
+          md(s.teacher_actions),
+          md(s.pupil_activities),
+        )).flatten()
+      )
+    }
+
+    // 6. EVALUATION
+    part("Evaluation")
+    [#md(lesson.summative_assessment)]
+    if lesson.theoretical_questions.len() > 0 {
+      enum(..lesson.theoretical_questions.map(q => md(q.question)))
+    }
+
+    // 7. ASSIGNMENT
+    part("Assignment")
+    list(..lesson.extension_activities.map(md))
+
+    // 8. SIGN-OFF
+    v(1.5em)
+    grid(
+      columns: (1fr, 1fr),
+      column-gutter: 2em,
+      [Teacher's Signature: #box(width: 1fr, line(length: 100%))],
+      [Head Teacher's Signature: #box(width: 1fr, line(length: 100%))],
+    )
+
+    pagebreak(weak: true)
+  }
+}
+
+// --- EXECUTE ---
+#lesson_notes(
+  branding: branding,
+  subject: subject_name,
+  year: class_year,
+  lessons: dummy_lessons,
+)
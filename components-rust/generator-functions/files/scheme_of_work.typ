@@ -0,0 +1,67 @@
+// --- CONFIGURATION FROM INPUT ---
+#import sys: inputs
+
+#let school_name = inputs.at("school_name", default: "")
+
+#let subject_name = inputs.at("subject_name", default: "")
+#let class_year = inputs.at("class_year", default: "")
+#let class_level = inputs.at("class_level", default: "")
+#let terms = inputs.at("terms", default: ())
+
+// Topics and objectives are sanitized typst markup, see utils::markup
+#let md(text) = eval(text, mode: "markup")
+
+// --- TEMPLATE DEFINITION ---
+#let scheme_of_work(
+  school: "",
+  subject: "",
+  year: "",
+  class_level: "",
+  terms: (),
+) = {
+  set document(title: subject + " Scheme of Work", author: school)
+  set text(font: "Linux Libertine", size: 11pt)
+  set page(
+    paper: "a4",
+    flipped: true,
+    margin: (x: 0.6in, y: 0.7in),
+    footer: context { align(center, text(9pt)[#counter(page).display("1")]) }
+  )
+
+  align(center)[
+    #text(16pt, weight: "bold")[#upper(school)] \
+    #text(14pt, weight: "bold")[SCHEME OF WORK] \
+    #text(12pt)[#upper(subject) - #if year != "" [YEAR #year] else [#class_level]]
+  ]
+  v(0.8em)
+
+  for entry in terms {
+    heading(level: 2)[#upper(str(entry.term)) TERM]
+    table(
+      columns: (auto, 1.3fr, 2fr, 1.3fr),
+      stroke: 0.5pt,
+      inset: 5pt,
+      fill: (col, row) => if row == 0 { silver.lighten(60%) },
+      [*Week*], [*Topic*], [*Objectives*], [*Materials*],
+      ..entry.weeks.map(w => (
+        str(w.week),
+        if w.generated { md(w.topic_title) } else {
+          [#md(w.topic_title) #text(9pt, style: "italic")[(planned)]]
+          if w.context != "" { [#linebreak()#text(9pt)[Context: #md(w.context)]] }
+        },
+        md(w.objectives),
+        md(w.materials),
+      )).flatten()
+    )
+    v(1em)
+  }
+}
+
+// --- EXECUTE ---
+#scheme_of_work(
+  school: school_name,
+  subject: subject_name,
+  year: class_year,
+  class_level: class_level,
+  terms: terms,
+)
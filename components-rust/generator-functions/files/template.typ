@@ -6,7 +6,19 @@
 #let subject_name = inputs.at("subject_name", default: "")
 #let class_year = inputs.at("class_year", default: "1")
 #let dummy_lessons = inputs.at("lessons", default: ())
-#let watermark_image = inputs.at("watermark_image", default: none)
+#let branding = inputs.at("branding")
+#let question_counts = inputs.at("question_counts", default: (mcq: 5, theory: 5))
+#let question_bank = inputs.at("question_bank", default: none)
+#let strings = inputs.at("strings")
+#let glossary_appendix = inputs.at("glossary_appendix", default: ())
+#let show_index = inputs.at("show_index", default: false)
+// False when printing part of a term (a week or some lessons), which gets no CA test pages
+#let ca_tests = inputs.at("ca_tests", default: true)
+// Page images of a single lesson for review, without front matter or term separators
+#let preview = inputs.at("preview", default: false)
+
+// Lesson text arrives as escaped typst markup (see utils/markup.rs), render it as such
+#let md(text) = eval(text, mode: "markup")
 
 // --- TEMPLATE DEFINITION ---
 #let project(
@@ -14,13 +26,24 @@
   year: "",
   mode: "pupil",
   lessons: (),
-  header_image: none,
-  watermark_image: none,
+  question_counts: (mcq: 5, theory: 5),
+  question_bank: none,
+  strings: (:),
+  glossary_appendix: (),
+  show_index: false,
+  ca_tests: true,
+  preview: false,
+  branding: (:),
 ) = {
+  let primary = rgb(branding.primary_colour)
+  let accent = rgb(branding.accent_colour)
+
   // 1. STYLES & WATERMARK
-  set document(title: subject + " Manual", author: "Johnethel School")
-  set text(font: "Times New Roman", size: 12pt)
+  set document(title: subject + " Manual", author: branding.name)
+  set text(font: "Linux Libertine", size: 12pt)
+  show math.equation: set text(font: "New Computer Modern Math")
   set par(justify: true)
+  show heading: set text(fill: primary)
 
   // Overlay function
   let overlay(img, color) = layout(bounds => {
@@ -33,20 +56,157 @@
     paper: "a4",
     margin: (x: 0.8in, y: 1in),
     background: context {
-      if watermark_image != none {
+      if branding.watermark != none {
         // place(center + center,
         //   block(
         //     fill: white.transparentize(100%),
         //     image(watermark_image, width: 80%),
         //   )
         // )
-        overlay(image(watermark_image, width: 80%), white.transparentize(5%))
+        overlay(image(branding.watermark, width: 80%), white.transparentize(5%))
       } else {
-        place(center + horizon, rotate(-45deg, text(100pt, fill: gray.lighten(85%), weight: "bold")[JOHNETHEL]))
+        place(center + horizon, rotate(-45deg, text(100pt, fill: gray.lighten(85%), weight: "bold")[#branding.short_name]))
       }
     }
   )
 
+  // Localized name of a term value ("FIRST", "SECOND", "THIRD")
+  let term_name(term) = if term == "FIRST" { strings.first_term } else if term == "SECOND" { strings.second_term } else if term == "THIRD" { strings.third_term } else { term + " TERM" }
+
+  // Translation printed under the English text in bilingual manuals
+  let tr(t) = if t != none [ \ #text(style: "italic", fill: luma(80))[#if type(t) == str { md(t) } else { t }]]
+  // Translated new word next to the English one
+  let glossary_tr(entry) = if entry.translated_term != none [ (#md(entry.translated_term))]
+
+  // Sub-points of a content section, nested sections going one level deeper.
+  // Level 1 is numbered (i), (ii)... and level 2 (a), (b)... unless the lesson gives its own marker.
+  let sub_points_list(sub_points, depth: 1) = {
+    let style = if calc.rem(depth, 2) == 1 { "(i)" } else { "(a)" }
+    for (n, sp) in sub_points.enumerate() {
+      let marker = if sp.sub_number != "" { md(sp.sub_number) } else { numbering(style, n + 1) }
+      let point = sp.text
+      pad(left: 1.2em * depth, grid(
+        columns: (2.4em, 1fr),
+        marker,
+        {
+          if point.header != none [*#md(point.header)* \ ]
+          [#md(point.body)#tr(point.translated_body)]
+          if point.sub_points != none and point.sub_points.len() > 0 {
+            sub_points_list(point.sub_points, depth: depth + 1)
+          }
+        },
+      ))
+    }
+  }
+
+  // Figures are described by the lesson as data and drawn here, so diagrams are always exact.
+  // Positions are measured from the top left of a w x h drawing area.
+  let label_at(w, h, x, y, body) = place(center + horizon, dx: x - w / 2, dy: y - h / 2, text(10pt, body))
+
+  let draw_shape(fig) = {
+    let (w, h) = (7cm, 5.5cm)
+    let (cx, cy) = (w / 2, h / 2)
+    let labels = fig.side_labels
+    box(width: w, height: h, {
+      if fig.shape == "circle" {
+        let r = 1.8cm
+        place(top + left, dx: cx - r, dy: cy - r, circle(radius: r, stroke: 1pt))
+        place(top + left, line(start: (cx, cy), end: (cx + r, cy), stroke: 1pt))
+        if labels.len() > 0 { label_at(w, h, cx + r / 2, cy - 0.35cm, md(labels.at(0))) }
+      } else if fig.shape == "square" or fig.shape == "rectangle" {
+        let (rw, rh) = if fig.shape == "square" { (3.2cm, 3.2cm) } else { (4.6cm, 2.8cm) }
+        let (x0, y0) = (cx - rw / 2, cy - rh / 2)
+        place(top + left, dx: x0, dy: y0, rect(width: rw, height: rh, stroke: 1pt))
+        // Sides in order: top, right, bottom, left
+        let spots = ((cx, y0 - 0.35cm), (x0 + rw + 0.7cm, cy), (cx, y0 + rh + 0.35cm), (x0 - 0.7cm, cy))
+        for (i, label) in labels.slice(0, calc.min(4, labels.len())).enumerate() {
+          let (x, y) = spots.at(i)
+          label_at(w, h, x, y, md(label))
+        }
+      } else {
+        // Regular polygon with a vertex at the top; side i joins vertex i and i + 1
+        let n = if fig.shape == "pentagon" { 5 } else if fig.shape == "hexagon" { 6 } else { 3 }
+        let r = 2cm
+        let corners = range(n).map(k => {
+          let a = -90deg + k * 360deg / n
+          (cx + r * calc.cos(a), cy + r * calc.sin(a))
+        })
+        place(top + left, polygon(stroke: 1pt, ..corners))
+        let d = r * calc.cos(180deg / n) + 0.45cm
+        for (i, label) in labels.slice(0, calc.min(n, labels.len())).enumerate() {
+          let a = -90deg + (i + 0.5) * 360deg / n
+          label_at(w, h, cx + d * calc.cos(a), cy + d * calc.sin(a), md(label))
+        }
+      }
+    })
+  }
+
+  let draw_number_line(fig) = {
+    let (w, h) = (14cm, 1.8cm)
+    let x(v) = 0.5cm + (v - fig.start) / (fig.end - fig.start) * (w - 1cm)
+    box(width: w, height: h, {
+      place(top + left, line(start: (0cm, 0.6cm), end: (w, 0.6cm), stroke: 1pt))
+      for v in range(fig.start, fig.end + 1, step: fig.step) {
+        place(top + left, line(start: (x(v), 0.4cm), end: (x(v), 0.8cm), stroke: 1pt))
+        label_at(w, h, x(v), 1.2cm, str(v))
+      }
+      for m in fig.marks {
+        place(top + left, dx: x(m) - 0.12cm, dy: 0.48cm, circle(radius: 0.12cm, fill: black))
+      }
+    })
+  }
+
+  let draw_bar_chart(fig) = {
+    let (bar, gap) = (1.1cm, 0.7cm)
+    let (w, h) = (1.6cm + fig.values.len() * (bar + gap), 6cm)
+    let (axis, base, tallest) = (1.2cm, 4.8cm, 4cm)
+    let top_value = calc.max(..fig.values)
+    box(width: w, height: h, {
+      place(top + left, line(start: (axis, 0.4cm), end: (axis, base), stroke: 1pt))
+      place(top + left, line(start: (axis, base), end: (w, base), stroke: 1pt))
+      if fig.y_label != none {
+        place(top + left, dx: 0cm, dy: 0cm, text(9pt, style: "italic", md(fig.y_label)))
+      }
+      for (i, v) in fig.values.enumerate() {
+        let bh = if top_value > 0 { tallest * v / top_value } else { 0cm }
+        let x0 = axis + gap / 2 + i * (bar + gap)
+        place(top + left, dx: x0, dy: base - bh, rect(width: bar, height: bh, fill: silver, stroke: 0.5pt))
+        label_at(w, h, x0 + bar / 2, base - bh - 0.3cm, text(9pt, str(v)))
+        label_at(w, h, x0 + bar / 2, base + 0.45cm, text(9pt, md(fig.categories.at(i))))
+      }
+    })
+  }
+
+  let draw_clock(fig) = {
+    let (w, h) = (5cm, 5cm)
+    let (cx, cy, r) = (w / 2, h / 2, 2.2cm)
+    box(width: w, height: h, {
+      place(top + left, dx: cx - r, dy: cy - r, circle(radius: r, stroke: 1.5pt))
+      for k in range(1, 13) {
+        let a = k * 30deg - 90deg
+        label_at(w, h, cx + 0.8 * r * calc.cos(a), cy + 0.8 * r * calc.sin(a), str(k))
+      }
+      let hour_angle = (calc.rem(fig.hour, 12) + fig.minute / 60) * 30deg - 90deg
+      let minute_angle = fig.minute * 6deg - 90deg
+      place(top + left, line(start: (cx, cy), length: 0.5 * r, angle: hour_angle, stroke: 2.5pt))
+      place(top + left, line(start: (cx, cy), length: 0.72 * r, angle: minute_angle, stroke: 1.2pt))
+      place(top + left, dx: cx - 0.08cm, dy: cy - 0.08cm, circle(radius: 0.08cm, fill: black))
+    })
+  }
+
+  let draw_figure(fig) = {
+    let drawing = if fig.kind == "SHAPE" {
+      draw_shape(fig)
+    } else if fig.kind == "NUMBER_LINE" {
+      draw_number_line(fig)
+    } else if fig.kind == "BAR_CHART" {
+      draw_bar_chart(fig)
+    } else {
+      draw_clock(fig)
+    }
+    figure(drawing, caption: md(fig.caption))
+  }
+
   // 2. HELPER: FULL-PAGE RULED LINES
   let full_page_lines(title: none) = context {
     // pagebreak(weak: true)
@@ -105,22 +265,22 @@
         // Years 1-2: Minimum 1 page, Maximum 1.5 pages
         if is_few_lines_remaining {
           // Few lines remaining: fill current page + add 1 full page
-          remaining_page_lines(title: "CLASS ACTIVITIES")
+          remaining_page_lines(title: strings.class_activities)
           full_page_lines()
         } else {
           // Lots of space remaining: just fill current page
-          remaining_page_lines(title: "CLASS ACTIVITIES")
+          remaining_page_lines(title: strings.class_activities)
         }
       } else {
-        // Years 3-5: Minimum 1.5 pages, Maximum 2 pages
+        // Years 3-5 and JSS: Minimum 1.5 pages, Maximum 2 pages
         if is_few_lines_remaining {
           // Few lines remaining: fill current page + add 2 full pages
-          remaining_page_lines(title: "CLASS ACTIVITIES")
+          remaining_page_lines(title: strings.class_activities)
           full_page_lines()
           full_page_lines()
         } else {
           // Lots of space remaining: fill current page + add 1 full page
-          remaining_page_lines(title: "CLASS ACTIVITIES")
+          remaining_page_lines(title: strings.class_activities)
           full_page_lines()
         }
       }
@@ -128,79 +288,62 @@
   }
 
 
-  // 3. FRONT MATTER
-  set page(numbering: "i", footer: context { align(center, text(10pt)[#counter(page).display("i")]) })
-
-  align(center + horizon)[
-    #text(22pt, weight: "bold")[Copyright Page] \ \ \
-    #text(18pt, weight: "bold")[JOHNETHEL SCHOOL MANUAL SERIES] \
-    #upper(subject) (YEAR #year) \ \ \ \
-    #text(12pt)[No part of this publication may be reproduced, distributed, or transmitted in any form or by any means, including photocopying, recording, or other electronic or mechanical methods, without the prior written permission of Johnethel School, except in the case of brief quotations embodied in critical reviews and certain other non-commercial uses permitted by copyright law.] \ \ \
-    #text(12pt)[*Published by:* Johnethel School \
-      *First Edition:* 2025 \
-      *Second Edition:* 2026
-    ] \ \ \ \
-    #text(12pt)[For permission requests, write to: Johnethel School \
-    Address: Omolabake House, beside Town Planning Office, Oke Anu, 		     Ogbomoso, Oyo State, Nigeria
-    Email: johnethelschool1\@gmail.com \
-    Phone: 08036674624, 09039465940
-    ] \ \ \ \ \
-    #text(12pt)[*Attributions:* Cover design includes 3D icons from vecteezy.com. Portions of this work were created with the assistance of an LLM.] \ \ \ \ \ \ \
-    #text(12pt)[*Disclaimer:* _The information contained in this manual is for educational purposes only. Johnethel School makes every effort to ensure the accuracy of the content but accepts no responsibility for any errors or omissions._]
-  ]
-  pagebreak()
-
-  align(center + horizon)[
-    #text(22pt, weight: "bold")[Instructions for Use] \ \
-    #align(left)[#text(15pt)[
-      *For Teachers* \
-      •	Use the manual as your primary teaching guide during lessons \
-      •	Follow the lesson aims and objectives provided in each section \
-      •	Assign homework from any section based on your teaching progress \
-      •	Record homework assignments in pupils' homework communication books \
-      •	Monitor pupil progress through practice questions and activities \
-      •	Ensure pupils have necessary materials: pencils, pens, colours, rulers, etc. \
-    ]] \
-    #align(left)[#text(15pt)[
-      *For Parents/Guardians* \
-      •	Provide a quiet, well-lit study space for homework \
-      •	Allow 15-20 minutes for homework sessions \
-      •	Help your child read instructions when needed \
-      •	Guide without giving direct answers \
-      •	Check and sign completed work in the homework communication book \
-      •	Contact school management if you have concerns about your child's progress \
-    ]] \
-    #align(left)[#text(15pt)[
-      *For Pupils/Students* \ \
-      *At School:* \
-      •	Listen carefully to your teacher's instructions \
-      •	Keep your manual clean and tidy \
-      •	Complete activities neatly \
-      •	Ask questions when you don't understand \ \ \
-      *At Home:* \
-      •	Find a quiet place for homework \
-      •	Read questions slowly \
-      •	Write answers clearly \
-      •	Complete work assigned by your teacher \
-      •	Ask your parents for help if needed \
-    ]]
-  ]
-
-  pagebreak()
-
-  heading(outlined: false)[Table of Contents]
-  outline(title: none, indent: 1.5em)
-  pagebreak()
+  // 3. FRONT MATTER (left out of single lesson previews)
+  if not preview {
+    set page(numbering: "i", footer: context { align(center, text(10pt)[#counter(page).display("i")]) })
+
+    align(center + horizon)[
+      #text(22pt, weight: "bold")[#strings.copyright_title] \ \ \
+      #text(18pt, weight: "bold")[#upper(strings.series_title)] \
+      #upper(subject) (#strings.year_label #year) \ \ \ \
+      #text(12pt)[#strings.copyright_notice] \ \ \
+      #if branding.logo != none { image(branding.logo, height: 2cm); linebreak() }
+      #text(12pt)[*#strings.published_by_label* #branding.name \
+        #for (i, year) in branding.editions.enumerate() {
+          let label = if i == 0 { strings.first_edition_label } else if i == 1 { strings.second_edition_label } else { strings.later_edition_label.replace("{n}", str(i + 1)) }
+          [*#label* #year \ ]
+        }
+      ] \ \ \
+      #text(12pt)[#strings.permission_requests #branding.name \
+      #strings.address_label #branding.address \
+      #strings.email_label #branding.email \
+      #strings.phone_label #branding.phones.join(", ")
+      ] \ \ \ \ \
+      #text(12pt)[*#strings.attributions_label* #strings.attributions] \ \ \ \ \ \ \
+      #text(12pt)[*#strings.disclaimer_label* #emph(strings.disclaimer)]
+    ]
+    pagebreak()
+
+    align(center + horizon)[
+      #text(22pt, weight: "bold")[#strings.instructions_title] \ \
+      #for group in strings.instructions {
+        align(left)[#text(15pt)[
+          *#group.title* \
+          #for section in group.sections {
+            if section.title != none [ \ *#section.title* \ ]
+            for item in section.items [•	#item \ ]
+          }
+        ]]
+        linebreak()
+      }
+    ]
+
+    pagebreak()
+
+    heading(outlined: false)[#strings.table_of_contents]
+    outline(title: none, indent: 1.5em)
+    pagebreak()
+  }
 
   // 4. MAIN CONTENT
   counter(page).update(1)
   set page(
     numbering: "1",
     header: context {
-      block(width: 100%, stroke: (bottom: 0.5pt), inset: (bottom: 8pt))[
+      block(width: 100%, stroke: (bottom: 0.5pt + accent), inset: (bottom: 8pt))[
         #set text(size: 10pt, weight: "bold")
-        #if header_image != none { image(header_image, width: 100%); v(-0.5em) }
-        JOHNETHEL #upper(subject) MANUAL (Year #year) #h(1fr) #text(style: "italic")[Grooming Future Leaders for Excellence]
+        #if branding.logo != none { box(image(branding.logo, height: 1.2em), baseline: 20%); h(0.4em) }
+        #branding.short_name #upper(subject) #strings.manual_label (#strings.year_label #year) #h(1fr) #text(style: "italic")[#branding.motto]
       ]
     },
     footer: context { align(center, text(10pt)[#counter(page).display("1")]) }
@@ -211,147 +354,189 @@
   for lesson in lessons {
 
     // CA Test Logic (Triggered before new term separator)
-    if last_term != none and lesson.term != last_term and mode == "pupil" {
+    if last_term != none and lesson.term != last_term and mode == "pupil" and ca_tests {
 
       for n in range(4) {
-        full_page_lines(title: last_term + " TERM: CA TEST " + str(n + 1))
+        full_page_lines(title: term_name(last_term) + ": " + strings.ca_test + " " + str(n + 1))
       }
     }
 
     // Term Separator
     if lesson.term != last_term {
-      pagebreak(weak: true)
-      set page(header: none)
-      align(center + horizon)[
-        #set text(size: 32pt, weight: "bold")
-        #heading(level: 1)[#upper(str(lesson.term)) TERM]
-      ]
-      pagebreak()
+      if not preview {
+        pagebreak(weak: true)
+        set page(header: none)
+        align(center + horizon)[
+          #set text(size: 32pt, weight: "bold")
+          #heading(level: 1)[#upper(term_name(lesson.term))]
+        ]
+        pagebreak()
+      }
       last_term = lesson.term
     }
 
     // --- LESSON HEADER ---
-    heading(level: 1)[TOPIC #lesson.week: #upper(lesson.topic_title)]
+    heading(level: 1)[#strings.topic_label #lesson.week: #upper(md(lesson.topic_title))]
+    if lesson.translated_topic_title != none {
+      text(14pt, style: "italic", weight: "bold")[#upper(md(lesson.translated_topic_title))]
+    }
+    // Invisible markers the index queries for page numbers
+    for term in lesson.index_terms [#metadata(term) <index-term>]
 
-    heading(level: 2)[LESSON OBJECTIVES]
-    list(..lesson.objectives.map(it => [#it.objective (#it.taxonomy_level)]))
+    heading(level: 2)[#strings.lesson_objectives]
+    list(..lesson.objectives.map(it => [#md(it.objective) (#md(it.taxonomy_level))#tr(it.translated_objective)]))
 
     if mode == "teacher" {
-      heading(level: 2)[PUPILS' PREVIOUS KNOWLEDGE]
-      list(..lesson.prior_knowledge.map(it => [#it]))
+      heading(level: 2)[#strings.previous_knowledge]
+      list(..lesson.prior_knowledge.map(md))
     }
 
     if mode == "teacher" {
-      heading(level: 2)[INSTRUCTIONAL MATERIALS]
-      list(..lesson.materials.map(it => [#it]))
+      heading(level: 2)[#strings.instructional_materials]
+      list(..lesson.materials.map(md))
     }
 
     if mode == "teacher" {
-      heading(level: 2)[TEACHER PREPARATION]
-      [*Materials:* #lesson.materials.join(", ")]; linebreak()
-      [*Duration:* #lesson.duration_mins mins]
+      heading(level: 2)[#strings.teacher_preparation]
+      [*#strings.materials_label* #lesson.materials.map(md).join([, ])]; linebreak()
+      [*#strings.duration_label* #lesson.duration_mins #strings.minutes]
     }
 
-    heading(level: 2)[LESSON CONTENT]
-    [#lesson.introduction]
+    heading(level: 2)[#strings.lesson_content]
+    [#md(lesson.introduction)#tr(lesson.translated_introduction)]
     for sec in lesson.content_sections {
       block()[
-      *#sec.header*
-      #par(sec.body)
-      #if sec.sub_points != none {
-        for sp in sec.sub_points {
-          // Handling potential string or dictionary for .text
-          let content = if type(sp.text) == dictionary { sp.text.body } else { sp.text }
-          [- #sp.sub_number. #content]
+      *#sec.section_number. #md(sec.header)*#tr(sec.translated_header)
+      #par[#md(sec.body)#tr(sec.translated_body)]
+      #for fig in sec.figures { draw_figure(fig) }
+      #if sec.image != none {
+        let picture = if sec.image.data != none {
+          image(sec.image.data, width: 60%)
+        } else {
+          // Placeholder until an image with this tag is added to the library
+          rect(width: 60%, height: 5cm, stroke: (dash: "dashed", paint: gray), align(center + horizon, text(fill: gray)[#sec.image.tag]))
         }
+        figure(picture, caption: if sec.image.caption != none { md(sec.image.caption) })
+      }
+      #for tbl in sec.tables {
+        figure(
+          kind: table,
+          caption: md(tbl.caption),
+          table(
+            columns: tbl.headers.len(),
+            fill: (col, row) => if row == 0 { silver.lighten(60%) },
+            ..tbl.headers.map(h => [*#md(h)*]),
+            ..tbl.rows.flatten().map(md)
+          ),
+        )
+      }
+      #if sec.sub_points != none {
+        sub_points_list(sec.sub_points)
       }
     ]
       v(0.5em)
     }
-    [*CONCLUSION*]; linebreak()
-    [#lesson.conclusion]
+    [*#strings.conclusion*]; linebreak()
+    [#md(lesson.conclusion)#tr(lesson.translated_conclusion)]
 
-    heading(level: 2)[Key Points:]
-    enum(..lesson.key_points)
+    heading(level: 2)[#strings.key_points]
+    if lesson.translated_key_points != none {
+      // Paired by position, a key point without a translation is printed on its own
+      enum(..lesson.key_points.enumerate().map(((i, kp)) => [#md(kp)#tr(lesson.translated_key_points.at(i, default: none))]))
+    } else {
+      enum(..lesson.key_points.map(md))
+    }
+
+    if mode == "pupil" and lesson.glossary.len() > 0 {
+      v(0.5em)
+      block(width: 100%, inset: 10pt, radius: 4pt, stroke: 1pt + accent, fill: luma(245))[
+        #text(weight: "bold")[#strings.new_words] \
+        #for entry in lesson.glossary [*#md(entry.term)#glossary_tr(entry):* #md(entry.definition)#tr(entry.translated_definition) \ ]
+      ]
+    }
 
     if mode == "teacher" and lesson.lesson_steps != none and lesson.lesson_steps.len() > 0 {
-      heading(level: 2)[LESSON STEPS]
+      heading(level: 2)[#strings.lesson_steps]
       table(
         columns: (auto, 1fr, 1.5fr, 1.5fr),
         fill: (col, row) => if row == 0 { silver.lighten(60%) },
-        [*Step*], [*Phase*], [*Teacher Actions*], [*Pupil Activities*],
-        ..lesson.lesson_steps.map(s => (str(s.step_number), s.phase, s.teacher_actions, s.pupil_activities)).flatten()
+        [*#strings.step_label*], [*#strings.phase_label*], [*#strings.teacher_actions*], [*#strings.pupil_activities*],
+        ..lesson.lesson_steps.map(s => (str(s.step_number), md(s.phase), md(s.teacher_actions), md(s.pupil_activities))).flatten()
       )
     }
 
     if mode == "teacher" {
-      heading(level: 2)[FORMATIVE ASSESSMENT]
-      [#lesson.formative_assessment]
+      heading(level: 2)[#strings.formative_assessment]
+      [#md(lesson.formative_assessment)]
     }
 
     if mode == "teacher" {
-      heading(level: 2)[SUMMATIVE ASSESSMENT]
-      [#lesson.summative_assessment]
+      heading(level: 2)[#strings.summative_assessment]
+      [#md(lesson.summative_assessment)]
     }
 
     if mode == "teacher" {
-      heading(level: 2)[SUCCESS CRITERIA]
-      list(..lesson.success_criteria.map(it => [#it]))
+      heading(level: 2)[#strings.success_criteria]
+      list(..lesson.success_criteria.map(md))
     }
 
     if lesson.mcq_questions.len() > 0 {
-      heading(level: 2)[REVISION QUESTIONS (Multiple Choice)]
+      heading(level: 2)[#strings.revision_questions]
       let q_count = 1
-      for q in lesson.mcq_questions {
-        if q_count > 5 {
-          break
+      for q in lesson.mcq_questions.slice(0, calc.min(question_counts.mcq, lesson.mcq_questions.len())) {
+        [#q_count. #md(q.question)#tr(q.translated_question) ]
+        [(a) #md(q.option_a) (b) #md(q.option_b) (c) #md(q.option_c)]
+        if q.translated_options != none {
+          let (ta, tb, tc) = q.translated_options
+          tr[(a) #md(ta) (b) #md(tb) (c) #md(tc)]
         }
-        [#q_count. #q.question ]
-        [(a) #q.option_a (b) #q.option_b (c) #q.option_c]
-        if mode == "teacher" { text(fill: blue, weight: "bold")[ [Ans: #q.correct_answer] \ Explanation: #q.explanation] }
+        if mode == "teacher" { text(fill: blue, weight: "bold")[ [#strings.answer_label #q.correct_answer] \ #strings.explanation_label #md(q.explanation)] }
         v(0.5em)
         q_count += 1
       }
     }
 
     if lesson.theoretical_questions.len() > 0 {
-      heading(level: 2)[THEORETICAL QUESTIONS]
+      heading(level: 2)[#strings.theoretical_questions]
       let q_count = 1
-      for q in lesson.theoretical_questions {
-        if q_count > 5 {
-          break
-        }
-        [#q_count. #q.question \ ]
+      for q in lesson.theoretical_questions.slice(0, calc.min(question_counts.theory, lesson.theoretical_questions.len())) {
+        [#q_count. #md(q.question)#tr(q.translated_question) \ ]
         for part in q.parts {
-          [#part ]; linebreak()
+          [#md(part) ]; linebreak()
+        }
+        if q.translated_parts != none {
+          for part in q.translated_parts {
+            text(style: "italic", fill: luma(80))[#md(part)]; linebreak()
+          }
         }
         // [(a) #q.option_a (b) #q.option_b (c) #q.option_c]
-        if mode == "teacher" { text(fill: blue, weight: "bold")[ Ans:\  #q.model_answer] }
+        if mode == "teacher" { text(fill: blue, weight: "bold")[ #strings.answer_label\  #md(q.model_answer)] }
         v(0.5em)
         q_count += 1
       }
     }
 
     if mode == "teacher" {
-      heading(level: 2)[EXTENSION ACTIVITIES]
-      list(..lesson.extension_activities.map(it => [#it]))
+      heading(level: 2)[#strings.extension_activities]
+      list(..lesson.extension_activities.map(md))
     }
 
     if mode == "teacher" {
-      heading(level: 2)[REMEDIATION (Support for struggling learners)]
-      [#lesson.remediation]
+      heading(level: 2)[#strings.remediation]
+      [#md(lesson.remediation)]
     }
 
     if mode == "teacher" {
-      heading(level: 2)[REFERENCES]
-      list(..lesson.textbook_references.map(it => [#it]))
+      heading(level: 2)[#strings.references]
+      list(..lesson.textbook_references.map(md))
     }
 
     // Activity Pages
     if mode == "pupil" {
-      let year_num = int(year)
+      // JSS classes have no year digit, they get the older pupils' activity pages
+      let year_num = if year == "" { 6 } else { int(year) }
       add_activity_pages(year_num: year_num)
-      // remaining_page_lines(title: "CLASS ACTIVITIES")
+      // remaining_page_lines(title: strings.class_activities)
       // full_page_lines()
     }
 
@@ -359,137 +544,83 @@
   }
 
   // Final Term CA Test
-  if mode == "pupil" and last_term != none {
+  if mode == "pupil" and last_term != none and ca_tests {
     for n in range(4) {
-      full_page_lines(title: last_term + " TERM: CA TEST " + str(n + 1))
+      full_page_lines(title: term_name(last_term) + ": " + strings.ca_test + " " + str(n + 1))
     }
   }
 
-  // let question_bank_last_term = none
-  // heading(level: 2)[EXAM QUESTIONS BANK]
-  // heading(level: 3)[REVISION QUESTIONS (Multiple Choice)]
-  // let objective_q_number = 1
-  // for lesson in lessons {
-  //   // term separator
-  //   if lesson.term != question_bank_last_term {
-  //     heading(level: 3)[#lesson.term]
-  //     question_bank_last_term = lesson.term
-  //   }
-  //   if mode == "teacher" {
-  //     if lesson.mcq_questions.len() > 0 {
-  //       let index = 0
-  //       for q in lesson.mcq_questions {
-  //         if index < 5 {
-  //           index += 1
-  //           continue
-  //         }
-  //         [#objective_q_number. #q.question ]
-  //         [(a) #q.option_a (b) #q.option_b (c) #q.option_c]
-  //         text(fill: blue, weight: "bold")[ [Ans: #q.correct_answer] \ Explanation: #q.explanation]
-  //         v(0.5em)
-  //         index += 1
-  //         objective_q_number += 1
-  //       }
-  //     }
-  //   }
-  // }
-
-  // heading(level: 3)[THEORETICAL QUESTIONS]
-  // let essay_q_number = 1
-  // for lesson in lessons {
-  //   if mode == "teacher" {
-  //     if lesson.theoretical_questions.len() > 0 {
-  //       let index = 0
-  //       for q in lesson.theoretical_questions {
-  //         if index < 5 {
-  //           index += 1
-  //           continue
-  //         }
-  //         [#q_number. #q.question \ ]
-  //         for part in q.parts {
-  //           [#part ]; linebreak()
-  //         }
-  //         // [(a) #q.option_a (b) #q.option_b (c) #q.option_c]
-  //         if mode == "teacher" { text(fill: blue, weight: "bold")[ Ans:\  #q.model_answer] }
-  //         v(0.5em)
-  //         index += 1
-  //         essay_q_number += 1
-  //       }
-  //     }
-  //   }
-  // }
-  //
-  if mode == "teacher" {
-    let question_bank_last_term = none
-    heading(level: 2)[EXAM QUESTIONS BANK]
-
-    // Get unique terms in order
-    let terms = ()
-    for lesson in lessons {
-      if not terms.contains(lesson.term) {
-        terms.push(lesson.term)
+  // 5. GLOSSARY APPENDIX
+  if glossary_appendix.len() > 0 {
+    pagebreak(weak: true)
+    heading(level: 1)[#strings.glossary]
+    let last_letter = none
+    for entry in glossary_appendix {
+      let letter = entry.letter
+      if letter != last_letter {
+        v(0.5em)
+        text(14pt, weight: "bold")[#letter]
+        linebreak()
+        last_letter = letter
       }
+      [*#md(entry.term)#glossary_tr(entry)* -- #md(entry.definition)#tr(entry.translated_definition)]
+      linebreak()
     }
+  }
 
-    // Process each term
-    for term in terms {
-      // Term heading
-      heading(level: 3)[#term TERM]
-
-      // Revision Questions section for this term
-      heading(level: 4)[REVISION QUESTIONS (Multiple Choice)]
-      let objective_q_number = 1
-
-      for lesson in lessons {
-        if lesson.term == term {
-          if mode == "teacher" {
-            if lesson.mcq_questions.len() > 0 {
-              let index = 0
-              for q in lesson.mcq_questions {
-                if index < 5 {
-                  index += 1
-                  continue
-                }
-                [#objective_q_number. #q.question ]
-                [(a) #q.option_a (b) #q.option_b (c) #q.option_c]
-                text(fill: blue, weight: "bold")[ [Ans: #q.correct_answer] \ Explanation: #q.explanation]
-                v(0.5em)
-                index += 1
-                objective_q_number += 1
-              }
-            }
+  // 6. INDEX (terms collected from lesson markers, with the pages they appear on)
+  if show_index {
+    pagebreak(weak: true)
+    heading(level: 1)[#strings.index]
+    context {
+      let entries = (:)
+      for marker in query(<index-term>) {
+        let page = counter(page).at(marker.location()).first()
+        let pages = entries.at(marker.value, default: ())
+        if page not in pages {
+          pages.push(page)
+        }
+        entries.insert(marker.value, pages)
+      }
+      columns(2)[
+        #for term in entries.keys().sorted(key: k => lower(k)) [
+          #md(term), #entries.at(term).map(str).join(", ") \
+        ]
+      ]
+    }
+  }
+
+  // 7. EXAM QUESTIONS BANK (questions beyond each lesson's share, pre-partitioned by term)
+  if question_bank != none and question_bank.len() > 0 {
+    pagebreak(weak: true)
+    heading(level: 1)[#strings.exam_questions_bank]
+
+    for entry in question_bank {
+      heading(level: 2)[#upper(term_name(entry.term))]
+
+      if entry.mcq_questions.len() > 0 {
+        heading(level: 3)[#strings.revision_questions]
+        for (i, q) in entry.mcq_questions.enumerate() {
+          [#(i + 1). #md(q.question)#tr(q.translated_question) ]
+          [(a) #md(q.option_a) (b) #md(q.option_b) (c) #md(q.option_c)]
+          if q.translated_options != none {
+            let (ta, tb, tc) = q.translated_options
+            tr[(a) #md(ta) (b) #md(tb) (c) #md(tc)]
           }
+          if mode == "teacher" { text(fill: blue, weight: "bold")[ [#strings.answer_label #q.correct_answer] \ #strings.explanation_label #md(q.explanation)] }
+          v(0.5em)
         }
       }
 
-      // Theoretical Questions section for this term
-      heading(level: 4)[THEORETICAL QUESTIONS]
-      let essay_q_number = 1
-
-      for lesson in lessons {
-        if lesson.term == term {
-          if mode == "teacher" {
-            if lesson.theoretical_questions.len() > 0 {
-              let index = 0
-              for q in lesson.theoretical_questions {
-                if index < 5 {
-                  index += 1
-                  continue
-                }
-                if index >= 6 {
-                  break
-                }
-                [#essay_q_number. #q.question \ ]
-                for part in q.parts {
-                  [#part ]; linebreak()
-                }
-                text(fill: blue, weight: "bold")[ Ans:\  #q.model_answer]
-                v(0.5em)
-                index += 1
-                essay_q_number += 1
-              }
-            }
+      if entry.theoretical_questions.len() > 0 {
+        heading(level: 3)[#strings.theoretical_questions]
+        for (i, q) in entry.theoretical_questions.enumerate() {
+          [#(i + 1). #md(q.question)#tr(q.translated_question) \ ]
+          for part in q.parts {
+            [#md(part) ]; linebreak()
           }
+          if mode == "teacher" { text(fill: blue, weight: "bold")[ #strings.answer_label\  #md(q.model_answer)] }
+          v(0.5em)
         }
       }
     }
@@ -502,6 +633,12 @@
   year: class_year,
   mode: mode,
   lessons: dummy_lessons,
-  header_image: none,
-  watermark_image: watermark_image
+  question_counts: question_counts,
+  question_bank: question_bank,
+  strings: strings,
+  glossary_appendix: glossary_appendix,
+  show_index: show_index,
+  ca_tests: ca_tests,
+  preview: preview,
+  branding: branding,
 )
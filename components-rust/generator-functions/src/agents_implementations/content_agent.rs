@@ -1,4 +1,8 @@
-use common_lib::utils::{create_row, fetch_topics, generate_lesson_with_baml};
+use common_lib::utils::{
+    create_asset_row, create_row, create_translation_row, fetch_lesson_records, fetch_topics,
+    generate_lesson_with_baml, parse_language, translate_lesson_with_baml, validate_table_name,
+    LessonFilter,
+};
 use common_lib::{AgentError, ContentAgent};
 use golem_rust::agent_implementation;
 use wstd::task::sleep;
@@ -14,11 +18,13 @@ use crate::ContentImpl;
 #[agent_implementation]
 impl ContentAgent for ContentImpl {
     fn new(name: String) -> Self {
-        Self { _name: name }
+        Self { name }
     }
 
     async fn content_generator(&mut self, table: String) -> Result<Vec<String>, AgentError> {
-        let term_topics = fetch_topics(table.as_str()).await?;
+        validate_table_name(table.as_str())?;
+        let tenant = self.tenant()?;
+        let term_topics = fetch_topics(&tenant, table.as_str()).await?;
         let mut resp_vec: Vec<String> = Vec::new();
         for topic in &term_topics {
             let generated_content =
@@ -31,7 +37,7 @@ impl ContentAgent for ContentImpl {
                         ),
                         code: "CONTENT_GENERATION_ERROR".to_string(),
                     })?;
-            let created_content = create_row(generated_content, topic.id.clone().unwrap())
+            let created_content = create_row(&tenant, generated_content, topic.id.clone().unwrap())
                 .await
                 .map_err(|e| AgentError {
                     message: format!(
@@ -53,6 +59,56 @@ impl ContentAgent for ContentImpl {
         Ok(resp_vec)
     }
 
+    async fn translate_lessons(
+        &mut self,
+        subject: String,
+        class: String,
+        language: String,
+    ) -> Result<Vec<String>, AgentError> {
+        let language = parse_language(language.as_str())?;
+        let tenant = self.tenant()?;
+        let lessons = fetch_lesson_records(
+            &tenant,
+            subject.as_str(),
+            class.as_str(),
+            &LessonFilter::default(),
+        )
+        .await?;
+        let mut resp_vec: Vec<String> = Vec::new();
+        for lesson in lessons {
+            let topic_title = lesson.content.topic_title.clone();
+            let translated = translate_lesson_with_baml(lesson.content, language)
+                .await
+                .map_err(|e| AgentError {
+                    message: format!("Failed to translate topic '{}': {:?}", topic_title, e),
+                    code: "CONTENT_TRANSLATION_ERROR".to_string(),
+                })?;
+            let created = create_translation_row(&tenant, translated, lesson.id, language)
+                .await
+                .map_err(|e| AgentError {
+                    message: format!(
+                        "Failed to store translation for topic '{}': {:?}",
+                        topic_title, e
+                    ),
+                    code: "CONTENT_DB_UPDATE_ERROR".to_string(),
+                })?;
+            resp_vec.push(created);
+
+            // Delay to avoid rate limiting
+            sleep(Duration::from_millis(4000)).await;
+        }
+        Ok(resp_vec)
+    }
+
+    async fn upload_asset(
+        &mut self,
+        tag: String,
+        content_type: String,
+        data: Vec<u8>,
+    ) -> Result<String, AgentError> {
+        create_asset_row(&self.tenant()?, tag.as_str(), content_type.as_str(), &data).await
+    }
+
     async fn test_sleep(&mut self) -> String {
         let stats = ["first print", "second print", "third print"];
         for statement in stats {
@@ -1,6 +1,19 @@
 use common_lib::{
-    utils::{fetch_lessons, pdf_engine::pdf_engine},
-    PdfAgent, PdfFile,
+    utils::{
+        answer_sheet::answer_sheet_engine,
+        cover::{cover_asset_tags, cover_engine},
+        fetch_assets, fetch_branding, fetch_lesson_records, fetch_lessons, fetch_template_versions,
+        fetch_topics, fetch_translations, fonts, lesson_image_tags, parse_language,
+        pdf_cache::{etag_matches, manual_cache_key, ManualCache, ManualOptions},
+        pdf_engine::{
+            docx::docx_engine, html::html_engine, pdf_engine, preview_engine, ManualTranslation,
+        },
+        save_branding,
+        scheme_of_work::scheme_of_work_engine,
+        templates::{builtin_template_names, register_template, resolve_template},
+        LessonFilter, LessonRecord, Tenant,
+    },
+    AgentError, CachedPdfFile, PdfAgent, PdfFile, PreviewPage, SchoolBranding,
 };
 use golem_rust::agent_implementation;
 
@@ -9,35 +22,601 @@ use crate::PdfImpl;
 #[agent_implementation]
 impl PdfAgent for PdfImpl {
     fn new(name: String) -> Self {
-        Self { _name: name }
+        Self {
+            name,
+            cache: ManualCache::default(),
+        }
+    }
+
+    async fn pdf_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        mode: String,
+        question_bank: Option<bool>,
+        language: Option<String>,
+        term: Option<String>,
+        weeks: Option<String>,
+        lesson_ids: Option<String>,
+        template: Option<String>,
+        if_none_match: Option<String>,
+    ) -> CachedPdfFile {
+        let question_bank = question_bank.unwrap_or(false);
+        let language = language.unwrap_or_default();
+        let template = template.unwrap_or_default();
+        let tenant = match self.tenant() {
+            Ok(tenant) => tenant,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let filter = match LessonFilter::parse(
+            term.as_deref().unwrap_or_default(),
+            weeks.as_deref().unwrap_or_default(),
+            lesson_ids.as_deref().unwrap_or_default(),
+        ) {
+            Ok(filter) => filter,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let manual_records =
+            fetch_lesson_records(&tenant, subject.as_str(), class.as_str(), &filter).await;
+        let manual_records = match manual_records {
+            Ok(records) => records,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let translation = match load_translation(&tenant, &manual_records, language.as_str()).await
+        {
+            Ok(translation) => translation,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let template = match resolve_template(&tenant, template.as_str(), mode.as_str()).await {
+            Ok(template) => template,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let manual_records: Vec<_> = manual_records.into_iter().map(|r| r.content).collect();
+        let branding = load_branding(&tenant).await;
+        let mut tags = lesson_image_tags(&manual_records);
+        tags.extend(
+            branding
+                .logo_tag
+                .iter()
+                .chain(&branding.watermark_tag)
+                .cloned(),
+        );
+        let assets = match fetch_assets(&tenant, &tags).await {
+            Ok(assets) => assets,
+            Err(err) => {
+                // Missing pictures fall back to placeholders, they shouldn't stop the manual
+                println!("⚠️  Could not fetch assets: {}", err.message);
+                Vec::new()
+            }
+        };
+        let options = ManualOptions {
+            subject_name: &subject,
+            class_year: &class,
+            mode: &mode,
+            question_bank,
+            ca_tests: filter.whole_terms(),
+        };
+        let cache_key = manual_cache_key(
+            &manual_records,
+            translation.as_ref(),
+            &template,
+            &assets,
+            &branding,
+            &options,
+        );
+        let cache_key = match cache_key {
+            Ok(key) => key,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let etag = format!("\"{}\"", cache_key);
+        // The client's copy was built from the same inputs
+        if etag_matches(if_none_match.as_deref().unwrap_or_default(), &cache_key) {
+            return CachedPdfFile {
+                status: 304,
+                etag,
+                content_type: "application/pdf".to_string(),
+                data: Vec::new(),
+            };
+        }
+        if let Some(pdf) = self.cache.get(&cache_key) {
+            println!("✓ Serving cached manual {}", cache_key);
+            return CachedPdfFile {
+                status: 200,
+                etag,
+                content_type: "application/pdf".to_string(),
+                data: pdf,
+            };
+        }
+
+        let pdf_bytes = pdf_engine(
+            manual_records,
+            translation,
+            assets,
+            branding,
+            &template,
+            &options,
+        );
+
+        match pdf_bytes {
+            Ok(pdf) => {
+                self.cache.insert(cache_key, pdf.clone());
+                CachedPdfFile {
+                    status: 200,
+                    etag,
+                    content_type: "application/pdf".to_string(),
+                    data: pdf,
+                }
+            }
+            Err(err) => {
+                println!("Error: {}", err.message);
+                err.into()
+            }
+        }
     }
 
-    async fn pdf_generator(&mut self, subject: String, class: String, mode: String) -> PdfFile {
-        let manual_records = fetch_lessons(subject.as_str(), class.as_str()).await;
+    async fn answer_sheet_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        term: String,
+        question_count: u32,
+        option_count: u32,
+    ) -> PdfFile {
+        let tenant = match self.tenant() {
+            Ok(tenant) => tenant,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let manual_records = fetch_lessons(
+            &tenant,
+            subject.as_str(),
+            class.as_str(),
+            &LessonFilter::default(),
+        )
+        .await;
         let manual_records = match manual_records {
             Ok(records) => records,
             Err(err) => {
                 println!("Error: {}", err.message);
-                return PdfFile {
-                    content_type: "text/plain".to_string(),
-                    data: err.message.into_bytes(),
-                };
+                return err.into();
             }
         };
-        let pdf_bytes = pdf_engine(manual_records, &subject, &class, &mode);
+        let pdf_bytes = answer_sheet_engine(
+            manual_records,
+            &subject,
+            &class,
+            &term,
+            question_count,
+            option_count,
+            &load_branding(&tenant).await.name,
+        );
 
         match pdf_bytes {
             Ok(pdf) => PdfFile {
+                status: 200,
                 content_type: "application/pdf".to_string(),
                 data: pdf,
             },
             Err(err) => {
                 println!("Error: {}", err.message);
-                PdfFile {
-                    content_type: "text/plain".to_string(),
-                    data: err.message.into_bytes(),
-                }
+                err.into()
+            }
+        }
+    }
+    async fn scheme_of_work_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        topics_table: String,
+    ) -> PdfFile {
+        let tenant = match self.tenant() {
+            Ok(tenant) => tenant,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let manual_records = fetch_lessons(
+            &tenant,
+            subject.as_str(),
+            class.as_str(),
+            &LessonFilter::default(),
+        )
+        .await;
+        let manual_records = match manual_records {
+            Ok(records) => records,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let topic_records = match fetch_topics(&tenant, topics_table.as_str()).await {
+            Ok(records) => records,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let branding = load_branding(&tenant).await;
+        let pdf_bytes = scheme_of_work_engine(
+            manual_records,
+            topic_records,
+            &subject,
+            &class,
+            &branding.name,
+        );
+
+        match pdf_bytes {
+            Ok(pdf) => PdfFile {
+                status: 200,
+                content_type: "application/pdf".to_string(),
+                data: pdf,
+            },
+            Err(err) => {
+                println!("Error: {}", err.message);
+                err.into()
             }
         }
     }
+
+    async fn lesson_preview(
+        &mut self,
+        subject: String,
+        class: String,
+        lesson_id: String,
+        mode: String,
+        page_count: u32,
+    ) -> Result<Vec<PreviewPage>, AgentError> {
+        let tenant = self.tenant()?;
+        let filter = LessonFilter::parse("", "", &lesson_id)?;
+        let lesson = fetch_lessons(&tenant, subject.as_str(), class.as_str(), &filter)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AgentError {
+                message: format!("No lesson {} for {} {}", lesson_id, subject, class),
+                code: "LESSON_NOT_FOUND".to_string(),
+            })?;
+        let template = resolve_template(&tenant, "", mode.as_str()).await?;
+        let branding = load_branding(&tenant).await;
+        let mut tags = lesson_image_tags(std::slice::from_ref(&lesson));
+        tags.extend(
+            branding
+                .logo_tag
+                .iter()
+                .chain(&branding.watermark_tag)
+                .cloned(),
+        );
+        let assets = match fetch_assets(&tenant, &tags).await {
+            Ok(assets) => assets,
+            Err(err) => {
+                println!("⚠️  Could not fetch assets: {}", err.message);
+                Vec::new()
+            }
+        };
+        let options = ManualOptions {
+            subject_name: &subject,
+            class_year: &class,
+            mode: &mode,
+            question_bank: false,
+            ca_tests: false,
+        };
+        let pages = preview_engine(
+            lesson,
+            assets,
+            branding,
+            &template,
+            &options,
+            page_count as usize,
+        )?;
+        Ok(pages
+            .into_iter()
+            .enumerate()
+            .map(|(i, png)| PreviewPage {
+                page_number: i as u32 + 1,
+                content_type: "image/png".to_string(),
+                data: png,
+            })
+            .collect())
+    }
+
+    async fn cover_generator(&mut self, subject: String, class: String, mode: String) -> PdfFile {
+        let tenant = match self.tenant() {
+            Ok(tenant) => tenant,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let branding = load_branding(&tenant).await;
+        let assets = match fetch_assets(&tenant, &cover_asset_tags(&branding, &subject)).await {
+            Ok(assets) => assets,
+            Err(err) => {
+                println!("⚠️  Could not fetch assets: {}", err.message);
+                Vec::new()
+            }
+        };
+        let pdf_bytes = cover_engine(&subject, &class, &mode, branding, assets);
+
+        match pdf_bytes {
+            Ok(pdf) => PdfFile {
+                status: 200,
+                content_type: "application/pdf".to_string(),
+                data: pdf,
+            },
+            Err(err) => {
+                println!("Error: {}", err.message);
+                err.into()
+            }
+        }
+    }
+
+    async fn html_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        mode: String,
+        language: Option<String>,
+        term: Option<String>,
+        weeks: Option<String>,
+        lesson_ids: Option<String>,
+    ) -> PdfFile {
+        let tenant = match self.tenant() {
+            Ok(tenant) => tenant,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let filter = match LessonFilter::parse(
+            term.as_deref().unwrap_or_default(),
+            weeks.as_deref().unwrap_or_default(),
+            lesson_ids.as_deref().unwrap_or_default(),
+        ) {
+            Ok(filter) => filter,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let html = manual_html(
+            &tenant,
+            &subject,
+            &class,
+            &mode,
+            language.as_deref().unwrap_or_default(),
+            &filter,
+        )
+        .await;
+
+        match html {
+            Ok(html) => PdfFile {
+                status: 200,
+                content_type: "text/html; charset=utf-8".to_string(),
+                data: html,
+            },
+            Err(err) => {
+                println!("Error: {}", err.message);
+                err.into()
+            }
+        }
+    }
+
+    async fn docx_generator(
+        &mut self,
+        subject: String,
+        class: String,
+        mode: String,
+        language: Option<String>,
+        term: Option<String>,
+        weeks: Option<String>,
+        lesson_ids: Option<String>,
+    ) -> PdfFile {
+        let tenant = match self.tenant() {
+            Ok(tenant) => tenant,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let filter = match LessonFilter::parse(
+            term.as_deref().unwrap_or_default(),
+            weeks.as_deref().unwrap_or_default(),
+            lesson_ids.as_deref().unwrap_or_default(),
+        ) {
+            Ok(filter) => filter,
+            Err(err) => {
+                println!("Error: {}", err.message);
+                return err.into();
+            }
+        };
+        let docx_bytes = manual_docx(
+            &tenant,
+            &subject,
+            &class,
+            &mode,
+            language.as_deref().unwrap_or_default(),
+            &filter,
+        )
+        .await;
+
+        match docx_bytes {
+            Ok(docx) => PdfFile {
+                status: 200,
+                content_type:
+                    "application/vnd.openxmlformats-officedocument.wordprocessingml.document"
+                        .to_string(),
+                data: docx,
+            },
+            Err(err) => {
+                println!("Error: {}", err.message);
+                err.into()
+            }
+        }
+    }
+
+    async fn get_branding(&mut self) -> Result<SchoolBranding, AgentError> {
+        fetch_branding(&self.tenant()?).await
+    }
+
+    async fn set_branding(&mut self, branding: SchoolBranding) -> Result<String, AgentError> {
+        save_branding(&self.tenant()?, branding).await
+    }
+
+    async fn upload_template(
+        &mut self,
+        name: String,
+        source: String,
+    ) -> Result<String, AgentError> {
+        register_template(&self.tenant()?, name.as_str(), source).await
+    }
+
+    // Built-in templates are version 0, uploads count up from 1
+    async fn list_templates(&mut self) -> Result<Vec<String>, AgentError> {
+        let mut templates: Vec<String> = builtin_template_names()
+            .into_iter()
+            .map(|name| format!("{}@0", name))
+            .collect();
+        templates.extend(
+            fetch_template_versions(&self.tenant()?)
+                .await?
+                .into_iter()
+                .map(|t| format!("{}@{}", t.name, t.version)),
+        );
+        Ok(templates)
+    }
+
+    async fn font_preflight(
+        &mut self,
+        subject: String,
+        class: String,
+        language: String,
+    ) -> Result<Vec<String>, AgentError> {
+        let tenant = self.tenant()?;
+        let records = fetch_lesson_records(
+            &tenant,
+            subject.as_str(),
+            class.as_str(),
+            &LessonFilter::default(),
+        )
+        .await?;
+        let mut missing =
+            fonts::font_preflight(&records.iter().map(|r| &r.content).collect::<Vec<_>>())?;
+        if let Some(translation) = load_translation(&tenant, &records, language.as_str()).await? {
+            let translated: Vec<_> = translation.lessons.into_iter().flatten().collect();
+            missing.extend(fonts::font_preflight(&translated)?);
+        }
+        Ok(missing)
+    }
+}
+
+// A manual is still worth printing with the default branding if the profile can't be read
+async fn load_branding(tenant: &Tenant) -> SchoolBranding {
+    match fetch_branding(tenant).await {
+        Ok(branding) => branding,
+        Err(err) => {
+            println!(
+                "⚠️  Could not fetch branding, using defaults: {}",
+                err.message
+            );
+            SchoolBranding::default()
+        }
+    }
+}
+
+// "english" (or empty) renders the English manual, "yoruba" a translated manual and
+// "english-yoruba" a bilingual one. Lessons without a stored translation stay in English.
+async fn load_translation(
+    tenant: &Tenant,
+    records: &[LessonRecord],
+    language: &str,
+) -> Result<Option<ManualTranslation>, AgentError> {
+    let language = language.to_lowercase();
+    let (language, bilingual) = match language.as_str() {
+        "" | "english" | "en" => return Ok(None),
+        other => match other.strip_prefix("english-") {
+            Some(target) => (parse_language(target)?, true),
+            None => (parse_language(other)?, false),
+        },
+    };
+
+    let lesson_ids: Vec<String> = records.iter().map(|r| r.id.clone()).collect();
+    let mut translations = fetch_translations(tenant, &lesson_ids, language).await?;
+    let lessons = records
+        .iter()
+        .map(|r| {
+            translations
+                .iter()
+                .position(|t| t.lesson_id == r.id)
+                .map(|i| translations.swap_remove(i).content)
+        })
+        .collect();
+
+    Ok(Some(ManualTranslation {
+        language,
+        bilingual,
+        lessons,
+    }))
+}
+
+// Lessons, translation, branding and images for html_engine, fetched the same way
+// pdf_generator fetches them
+async fn manual_html(
+    tenant: &Tenant,
+    subject: &str,
+    class: &str,
+    mode: &str,
+    language: &str,
+    filter: &LessonFilter,
+) -> Result<Vec<u8>, AgentError> {
+    let records = fetch_lesson_records(tenant, subject, class, filter).await?;
+    let translation = load_translation(tenant, &records, language).await?;
+    let lessons: Vec<_> = records.into_iter().map(|r| r.content).collect();
+    let branding = load_branding(tenant).await;
+    let mut tags = lesson_image_tags(&lessons);
+    tags.extend(branding.logo_tag.iter().cloned());
+    let assets = match fetch_assets(tenant, &tags).await {
+        Ok(assets) => assets,
+        Err(err) => {
+            // Missing pictures fall back to placeholders, they shouldn't stop the page
+            println!("⚠️  Could not fetch assets: {}", err.message);
+            Vec::new()
+        }
+    };
+    html_engine(lessons, subject, class, mode, translation, assets, branding)
+}
+
+// Lessons and translation for docx_engine, fetched the same way manual_html fetches them
+async fn manual_docx(
+    tenant: &Tenant,
+    subject: &str,
+    class: &str,
+    mode: &str,
+    language: &str,
+    filter: &LessonFilter,
+) -> Result<Vec<u8>, AgentError> {
+    let records = fetch_lesson_records(tenant, subject, class, filter).await?;
+    let translation = load_translation(tenant, &records, language).await?;
+    let lessons = records.into_iter().map(|r| r.content).collect();
+    docx_engine(lessons, subject, class, mode, translation)
 }
@@ -1,9 +1,27 @@
+use common_lib::utils::pdf_cache::ManualCache;
+use common_lib::utils::Tenant;
+use common_lib::AgentError;
+
 mod agents_implementations;
 
 pub struct ContentImpl {
-    _name: String,
+    name: String,
 }
 
 pub struct PdfImpl {
-    _name: String,
+    name: String,
+    cache: ManualCache,
+}
+
+// Agents are named "<tenant>:<name>"; the tenant picks the school's database
+impl ContentImpl {
+    fn tenant(&self) -> Result<Tenant, AgentError> {
+        Tenant::from_agent_name(&self.name)
+    }
+}
+
+impl PdfImpl {
+    fn tenant(&self) -> Result<Tenant, AgentError> {
+        Tenant::from_agent_name(&self.name)
+    }
 }